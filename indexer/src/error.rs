@@ -0,0 +1,36 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum IndexerError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    // the dump is valid json but not a getTransaction response
+    MalformedTransaction(String),
+    // a `Program data:` line from arc that could not be decoded
+    MalformedEvent(String),
+}
+
+impl fmt::Display for IndexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexerError::Io(e) => write!(f, "io error: {}", e),
+            IndexerError::Json(e) => write!(f, "json error: {}", e),
+            IndexerError::MalformedTransaction(msg) => write!(f, "malformed transaction: {}", msg),
+            IndexerError::MalformedEvent(msg) => write!(f, "malformed event: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for IndexerError {}
+
+impl From<std::io::Error> for IndexerError {
+    fn from(e: std::io::Error) -> Self {
+        IndexerError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for IndexerError {
+    fn from(e: serde_json::Error) -> Self {
+        IndexerError::Json(e)
+    }
+}
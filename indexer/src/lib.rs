@@ -0,0 +1,8 @@
+pub mod error;
+pub mod model;
+pub mod output;
+pub mod parse;
+
+pub use error::IndexerError;
+pub use model::{Model, PoolSnapshot};
+pub use parse::{parse_dump, Event, Record};
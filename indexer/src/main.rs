@@ -0,0 +1,64 @@
+use std::{fs, path::PathBuf, process};
+
+use arc_indexer::{output, parse_dump, IndexerError, Model};
+
+const USAGE: &str = "usage: arc-indexer [--csv] <dump.json | dump-dir>...";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), IndexerError> {
+    let mut csv = false;
+    let mut paths = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--csv" => csv = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let mut records = Vec::new();
+    for path in dump_files(paths)? {
+        records.extend(parse_dump(&fs::read_to_string(&path)?)?);
+    }
+
+    let mut model = Model::new();
+    model.apply_all(&mut records);
+    let snapshots = model.snapshots();
+    if csv {
+        print!("{}", output::to_csv(&snapshots));
+    } else {
+        println!("{}", output::to_json(&snapshots)?);
+    }
+    Ok(())
+}
+
+// directories expand to their .json files in name order
+fn dump_files(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>, IndexerError> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries = fs::read_dir(&path)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.retain(|p| p.extension().and_then(|ext| ext.to_str()) == Some("json"));
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
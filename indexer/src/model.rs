@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use anchor_lang::prelude::Pubkey;
+use serde::{Deserialize, Serialize};
+
+use crate::parse::{Event, Record};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoolSnapshot {
+    pub pool: String,
+    // unknown when the dump starts after the pool was created
+    pub owner: Option<String>,
+    pub mint: Option<String>,
+    pub collection: Option<String>,
+    pub curve_type: Option<u8>,
+    pub pool_type: Option<u8>,
+    pub delta: Option<i64>,
+    pub fee_bps: Option<u64>,
+    pub spot_price: u64,
+    pub n_nft: u64,
+    pub n_token: u64,
+    pub buys: u64,
+    pub sells: u64,
+    pub volume: u64,
+    pub pool_fees: u64,
+    pub curator_fees: u64,
+    pub closed: bool,
+    pub last_slot: u64,
+}
+
+#[derive(Default)]
+pub struct Model {
+    pools: BTreeMap<Pubkey, PoolSnapshot>,
+}
+
+impl Model {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // records are applied in slot order, keeping log order within a slot
+    pub fn apply_all(&mut self, records: &mut [Record]) {
+        records.sort_by_key(|r| r.slot);
+        for record in records.iter() {
+            self.apply(record);
+        }
+    }
+
+    pub fn apply(&mut self, record: &Record) {
+        let pool_key = record.event.pool();
+        let pool = self.pools.entry(pool_key).or_insert_with(|| PoolSnapshot {
+            pool: pool_key.to_string(),
+            ..PoolSnapshot::default()
+        });
+        pool.last_slot = pool.last_slot.max(record.slot);
+
+        match &record.event {
+            Event::PoolCreated(e) => {
+                pool.owner = Some(e.owner.to_string());
+                pool.mint = Some(e.mint.to_string());
+                pool.collection = e.collection.map(|c| c.to_string());
+                pool.curve_type = Some(e.curve_type);
+                pool.pool_type = Some(e.pool_type);
+                pool.delta = Some(e.delta);
+                pool.fee_bps = Some(e.fee_bps);
+                pool.spot_price = e.spot_price;
+                pool.closed = false;
+            }
+            Event::PoolClosed(_) => {
+                pool.closed = true;
+            }
+            Event::NftDeposited(e) => pool.n_nft = e.n_nft,
+            Event::NftWithdrawn(e) => pool.n_nft = e.n_nft,
+            Event::TokenDeposited(e) => pool.n_token = e.n_token,
+            Event::TokenWithdrawn(e) => pool.n_token = e.n_token,
+            Event::Swap(e) => {
+                if e.buy {
+                    pool.buys += 1;
+                } else {
+                    pool.sells += 1;
+                }
+                pool.volume += e.price;
+                pool.pool_fees += e.pool_fee;
+                pool.curator_fees += e.curator_fee;
+                pool.spot_price = e.spot_price;
+                pool.n_nft = e.n_nft;
+                pool.n_token = e.n_token;
+            }
+            Event::Rented(e) => {
+                pool.n_nft = e.n_nft;
+                pool.n_token = e.n_token;
+            }
+            Event::RentalReturned(e) => pool.n_nft = e.n_nft,
+            Event::OracleSynced(e) => pool.spot_price = e.spot_price,
+        }
+    }
+
+    pub fn get(&self, pool: &Pubkey) -> Option<&PoolSnapshot> {
+        self.pools.get(pool)
+    }
+
+    pub fn snapshots(&self) -> Vec<PoolSnapshot> {
+        self.pools.values().cloned().collect()
+    }
+}
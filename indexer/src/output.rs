@@ -0,0 +1,43 @@
+use crate::{error::IndexerError, model::PoolSnapshot};
+
+pub const CSV_HEADER: &str = "pool,owner,mint,collection,curve_type,pool_type,delta,fee_bps,\
+spot_price,n_nft,n_token,buys,sells,volume,pool_fees,curator_fees,closed,last_slot";
+
+pub fn to_json(snapshots: &[PoolSnapshot]) -> Result<String, IndexerError> {
+    Ok(serde_json::to_string_pretty(snapshots)?)
+}
+
+// every field is a pubkey, number or bool, so nothing needs quoting
+pub fn to_csv(snapshots: &[PoolSnapshot]) -> String {
+    fn opt<T: ToString>(value: &Option<T>) -> String {
+        value.as_ref().map(T::to_string).unwrap_or_default()
+    }
+
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for s in snapshots {
+        let row = [
+            s.pool.clone(),
+            opt(&s.owner),
+            opt(&s.mint),
+            opt(&s.collection),
+            opt(&s.curve_type),
+            opt(&s.pool_type),
+            opt(&s.delta),
+            opt(&s.fee_bps),
+            s.spot_price.to_string(),
+            s.n_nft.to_string(),
+            s.n_token.to_string(),
+            s.buys.to_string(),
+            s.sells.to_string(),
+            s.volume.to_string(),
+            s.pool_fees.to_string(),
+            s.curator_fees.to_string(),
+            s.closed.to_string(),
+            s.last_slot.to_string(),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
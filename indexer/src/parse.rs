@@ -0,0 +1,173 @@
+use anchor_lang::{prelude::Pubkey, AnchorDeserialize, Discriminator};
+use arc::events::{
+    NftDeposited, NftWithdrawn, OracleSynced, PoolClosed, PoolCreated, RentalReturned, Rented,
+    Swap, TokenDeposited, TokenWithdrawn,
+};
+use serde_json::Value;
+
+use crate::error::IndexerError;
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+pub enum Event {
+    PoolCreated(PoolCreated),
+    PoolClosed(PoolClosed),
+    NftDeposited(NftDeposited),
+    NftWithdrawn(NftWithdrawn),
+    TokenDeposited(TokenDeposited),
+    TokenWithdrawn(TokenWithdrawn),
+    Swap(Swap),
+    Rented(Rented),
+    RentalReturned(RentalReturned),
+    OracleSynced(OracleSynced),
+}
+
+impl Event {
+    pub fn pool(&self) -> Pubkey {
+        match self {
+            Event::PoolCreated(e) => e.pool,
+            Event::PoolClosed(e) => e.pool,
+            Event::NftDeposited(e) => e.pool,
+            Event::NftWithdrawn(e) => e.pool,
+            Event::TokenDeposited(e) => e.pool,
+            Event::TokenWithdrawn(e) => e.pool,
+            Event::Swap(e) => e.pool,
+            Event::Rented(e) => e.pool,
+            Event::RentalReturned(e) => e.pool,
+            Event::OracleSynced(e) => e.pool,
+        }
+    }
+}
+
+pub struct Record {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub event: Event,
+}
+
+// a dump is one getTransaction response, the bare `result`, or an array of either
+pub fn parse_dump(json: &str) -> Result<Vec<Record>, IndexerError> {
+    let value: Value = serde_json::from_str(json)?;
+    let mut records = Vec::new();
+    match value {
+        Value::Array(txs) => {
+            for tx in txs.iter() {
+                records.extend(parse_transaction(tx)?);
+            }
+        }
+        tx => records.extend(parse_transaction(&tx)?),
+    }
+    Ok(records)
+}
+
+pub fn parse_transaction(value: &Value) -> Result<Vec<Record>, IndexerError> {
+    let tx = value.get("result").unwrap_or(value);
+    if tx.is_null() {
+        return Err(IndexerError::MalformedTransaction(
+            "transaction not found".to_string(),
+        ));
+    }
+
+    let meta = tx
+        .get("meta")
+        .ok_or_else(|| IndexerError::MalformedTransaction("missing meta".to_string()))?;
+    // failed transactions roll back, their logs describe state that never landed
+    if !matches!(meta.get("err"), None | Some(Value::Null)) {
+        return Ok(Vec::new());
+    }
+
+    let slot = tx
+        .get("slot")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| IndexerError::MalformedTransaction("missing slot".to_string()))?;
+    let block_time = tx.get("blockTime").and_then(Value::as_i64);
+    let signature = tx
+        .pointer("/transaction/signatures/0")
+        .and_then(Value::as_str)
+        .ok_or_else(|| IndexerError::MalformedTransaction("missing signature".to_string()))?
+        .to_string();
+    let logs = meta
+        .get("logMessages")
+        .and_then(Value::as_array)
+        .ok_or_else(|| IndexerError::MalformedTransaction("missing logMessages".to_string()))?;
+
+    let mut records = Vec::new();
+    for event in parse_logs(logs.iter().filter_map(Value::as_str))? {
+        records.push(Record {
+            signature: signature.clone(),
+            slot,
+            block_time,
+            event,
+        });
+    }
+    Ok(records)
+}
+
+// `Program data:` lines belong to whichever program is on top of the invoke stack
+pub fn parse_logs<'a>(logs: impl Iterator<Item = &'a str>) -> Result<Vec<Event>, IndexerError> {
+    let arc_id = arc::ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    for line in logs {
+        if let Some(data) = line.strip_prefix(PROGRAM_DATA) {
+            if stack.last() == Some(&arc_id.as_str()) {
+                events.extend(decode_event(data)?);
+            }
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        if words.next() != Some("Program") {
+            continue;
+        }
+        let (program, action) = match (words.next(), words.next()) {
+            (Some(program), Some(action)) => (program, action),
+            _ => continue,
+        };
+        match action {
+            "invoke" => stack.push(program),
+            "success" | "failed:" => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    Ok(events)
+}
+
+// unknown discriminators are skipped so newer program versions don't break old dumps
+pub fn decode_event(data: &str) -> Result<Option<Event>, IndexerError> {
+    let bytes =
+        base64::decode(data.trim()).map_err(|e| IndexerError::MalformedEvent(e.to_string()))?;
+    if bytes.len() < 8 {
+        return Err(IndexerError::MalformedEvent(
+            "shorter than discriminator".to_string(),
+        ));
+    }
+    let (discriminator, mut body) = bytes.split_at(8);
+
+    macro_rules! decode {
+        ($($ty:ident),*) => {
+            $(
+                if discriminator == $ty::discriminator() {
+                    return $ty::deserialize(&mut body)
+                        .map(|e| Some(Event::$ty(e)))
+                        .map_err(|e| IndexerError::MalformedEvent(e.to_string()));
+                }
+            )*
+        };
+    }
+    decode!(
+        PoolCreated,
+        PoolClosed,
+        NftDeposited,
+        NftWithdrawn,
+        TokenDeposited,
+        TokenWithdrawn,
+        Swap,
+        Rented,
+        RentalReturned,
+        OracleSynced
+    );
+    Ok(None)
+}
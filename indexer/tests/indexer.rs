@@ -0,0 +1,240 @@
+use std::{fs, path::PathBuf};
+
+use anchor_lang::{prelude::Pubkey, AnchorSerialize, Discriminator};
+use arc::events::{RentalReturned, Rented};
+use arc_indexer::{
+    output::{self, CSV_HEADER},
+    parse::parse_logs,
+    parse_dump, Event, Model, PoolSnapshot, Record,
+};
+
+fn fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    fs::read_to_string(path).unwrap()
+}
+
+fn all_records() -> Vec<Record> {
+    let mut names: Vec<_> =
+        fs::read_dir(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+    names.sort();
+    names
+        .iter()
+        .flat_map(|name| parse_dump(&fixture(name)).unwrap())
+        .collect()
+}
+
+fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+fn indexed() -> Model {
+    let mut records = all_records();
+    let mut model = Model::new();
+    model.apply_all(&mut records);
+    model
+}
+
+#[test]
+fn parses_wrapped_response() {
+    let records = parse_dump(&fixture("tx_001_create.json")).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].signature, "sig1");
+    assert_eq!(records[0].slot, 100);
+    assert_eq!(records[0].block_time, Some(1660000100));
+    match &records[0].event {
+        Event::PoolCreated(e) => {
+            assert_eq!(e.pool, key(1));
+            assert_eq!(e.collection, Some(key(4)));
+            assert_eq!(e.spot_price, 100);
+        }
+        _ => panic!("expected PoolCreated"),
+    }
+}
+
+#[test]
+fn parses_arrays_of_transactions() {
+    let records = parse_dump(&fixture("tx_002_deposits.json")).unwrap();
+    assert_eq!(records.len(), 3);
+    assert!(matches!(records[0].event, Event::NftDeposited(_)));
+    assert!(matches!(records[2].event, Event::TokenDeposited(_)));
+}
+
+#[test]
+fn ignores_data_logged_by_other_programs() {
+    let records = parse_dump(&fixture("tx_003_swaps.json")).unwrap();
+    assert_eq!(records.len(), 2);
+    assert!(records.iter().all(|r| matches!(r.event, Event::Swap(_))));
+}
+
+#[test]
+fn skips_failed_transactions() {
+    let records = parse_dump(&fixture("tx_004_failed.json")).unwrap();
+    assert!(records.is_empty());
+}
+
+#[test]
+fn skips_unknown_events() {
+    let arc_id = arc::ID.to_string();
+    let logs = [
+        format!("Program {} invoke [1]", arc_id),
+        "Program data: AAAAAAAAAAAAAAAA".to_string(),
+        format!("Program {} success", arc_id),
+    ];
+    let events = parse_logs(logs.iter().map(String::as_str)).unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn rejects_truncated_events() {
+    let arc_id = arc::ID.to_string();
+    let logs = [
+        format!("Program {} invoke [1]", arc_id),
+        "Program data: AAAA".to_string(),
+        format!("Program {} success", arc_id),
+    ];
+    assert!(parse_logs(logs.iter().map(String::as_str)).is_err());
+}
+
+#[test]
+fn rejects_non_transactions() {
+    assert!(parse_dump(r#"{"jsonrpc": "2.0", "result": null}"#).is_err());
+    assert!(parse_dump(r#"{"slot": 1}"#).is_err());
+}
+
+#[test]
+fn replays_trades_into_pool_state() {
+    let model = indexed();
+    let pool = model.get(&key(1)).unwrap();
+    assert_eq!(
+        *pool,
+        PoolSnapshot {
+            pool: key(1).to_string(),
+            owner: Some(key(2).to_string()),
+            mint: Some(key(3).to_string()),
+            collection: Some(key(4).to_string()),
+            curve_type: Some(0),
+            pool_type: Some(2),
+            delta: Some(10),
+            fee_bps: Some(100),
+            spot_price: 100,
+            n_nft: 2,
+            n_token: 990,
+            buys: 1,
+            sells: 1,
+            volume: 210,
+            pool_fees: 2,
+            curator_fees: 0,
+            closed: false,
+            last_slot: 104,
+        }
+    );
+}
+
+#[test]
+fn rentals_move_nfts_out_and_back() {
+    let arc_id = arc::ID.to_string();
+    let data = |discriminator: [u8; 8], body: Vec<u8>| {
+        format!(
+            "Program data: {}",
+            base64::encode([&discriminator[..], &body].concat())
+        )
+    };
+    let rented = Rented {
+        pool: key(1),
+        nft_mint: key(9),
+        renter: key(10),
+        fee: 30,
+        expires_at: 1660100000,
+        n_nft: 1,
+        n_token: 1020,
+    };
+    let returned = RentalReturned {
+        pool: key(1),
+        nft_mint: key(9),
+        n_nft: 2,
+    };
+    let logs = [
+        format!("Program {} invoke [1]", arc_id),
+        data(Rented::discriminator(), rented.try_to_vec().unwrap()),
+        data(
+            RentalReturned::discriminator(),
+            returned.try_to_vec().unwrap(),
+        ),
+        format!("Program {} success", arc_id),
+    ];
+    let events = parse_logs(logs.iter().map(String::as_str)).unwrap();
+    assert_eq!(events.len(), 2);
+
+    let records: Vec<Record> = events
+        .into_iter()
+        .enumerate()
+        .map(|(i, event)| Record {
+            signature: "sig_rent".to_string(),
+            slot: 200 + i as u64,
+            block_time: None,
+            event,
+        })
+        .collect();
+
+    let mut model = indexed();
+    model.apply(&records[0]);
+    assert_eq!(model.get(&key(1)).unwrap().n_nft, 1);
+    assert_eq!(model.get(&key(1)).unwrap().n_token, 1020);
+    model.apply(&records[1]);
+    assert_eq!(model.get(&key(1)).unwrap().n_nft, 2);
+}
+
+#[test]
+fn tracks_closed_and_preexisting_pools() {
+    let model = indexed();
+    assert!(model.get(&key(5)).unwrap().closed);
+
+    let late = model.get(&key(6)).unwrap();
+    assert_eq!(late.owner, None);
+    assert_eq!(late.n_nft, 3);
+    assert_eq!(late.last_slot, 108);
+}
+
+#[test]
+fn applies_records_in_slot_order() {
+    let mut records = all_records();
+    records.reverse();
+    let mut model = Model::new();
+    model.apply_all(&mut records);
+    assert_eq!(model.snapshots(), indexed().snapshots());
+}
+
+#[test]
+fn writes_csv() {
+    let snapshots = indexed().snapshots();
+    let csv = output::to_csv(&snapshots);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], CSV_HEADER);
+    assert_eq!(lines.len(), snapshots.len() + 1);
+
+    let header: Vec<&str> = CSV_HEADER.split(',').collect();
+    let row: Vec<&str> = lines
+        .iter()
+        .find(|l| l.starts_with(&key(1).to_string()))
+        .unwrap()
+        .split(',')
+        .collect();
+    assert_eq!(row.len(), header.len());
+    let field = |name: &str| row[header.iter().position(|h| *h == name).unwrap()];
+    assert_eq!(field("spot_price"), "100");
+    assert_eq!(field("n_token"), "990");
+    assert_eq!(field("closed"), "false");
+}
+
+#[test]
+fn writes_json() {
+    let snapshots = indexed().snapshots();
+    let json = output::to_json(&snapshots).unwrap();
+    let parsed: Vec<PoolSnapshot> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, snapshots);
+}
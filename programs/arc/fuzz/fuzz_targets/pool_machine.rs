@@ -0,0 +1,44 @@
+#![no_main]
+
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+use arbitrary::{Result, Unstructured};
+use common::{check_round_trips, Op, PoolConfig, Sim};
+use libfuzzer_sys::fuzz_target;
+
+fn config(u: &mut Unstructured) -> Result<PoolConfig> {
+    Ok(PoolConfig {
+        curve_type: u.arbitrary()?,
+        pool_type: u.arbitrary()?,
+        spot_price: u.arbitrary()?,
+        delta: u.arbitrary()?,
+        fee_bps: u.arbitrary()?,
+        curator_fee_bps: u.arbitrary()?,
+        floor_price: u.arbitrary()?,
+        decay_rate: u.arbitrary()?,
+        virtual_token_reserve: u.arbitrary()?,
+        virtual_nft_reserve: u.arbitrary()?,
+        min_price: u.arbitrary()?,
+        max_price: u.arbitrary()?,
+        spread_bps: u.arbitrary()?,
+        flash_fee: u.arbitrary()?,
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut u = Unstructured::new(data);
+    let config = match config(&mut u) {
+        Ok(config) => config,
+        Err(_) => return,
+    };
+
+    let mut sim = Sim::new(config.arbitrary().build());
+    while let Ok((tag, arg)) = u.arbitrary::<(u8, u64)>() {
+        if u.is_empty() {
+            break;
+        }
+        sim.step(&Op::from_raw(tag, arg));
+        check_round_trips(&sim.pool);
+    }
+});
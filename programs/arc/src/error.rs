@@ -28,5 +28,101 @@ pub enum ErrorCode {
     InvalidDelta,
     #[msg("Invalid proof")]
     InvalidProof,
+    #[msg("Route price outside slippage bound")]
+    SlippageExceeded,
+    #[msg("Invalid route accounts")]
+    InvalidRouteAccounts,
+    #[msg("Pools do not share a collection")]
+    CollectionMismatch,
+    #[msg("Bid expired")]
+    BidExpired,
+    #[msg("Bid already filled")]
+    BidFilled,
+    #[msg("Pool already holds liquidity")]
+    PoolNotEmpty,
+    #[msg("Pool liquidity is shared, use lp instructions")]
+    SharedPool,
+    #[msg("Inventory page is full, init the next page")]
+    InventoryFull,
+    #[msg("Nft not in pool inventory")]
+    NotInInventory,
+    #[msg("Collection index page is full, init the next page")]
+    CollectionIndexFull,
+    #[msg("Pool not in collection index")]
+    NotInCollectionIndex,
+    #[msg("Wrong collection index for pool")]
+    CollectionIndexMismatch,
+    #[msg("Pool cannot cover trade")]
+    InsufficientLiquidity,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Nft vault not empty")]
+    VaultNotEmpty,
+    #[msg("Pool holds no nfts")]
+    InventoryUnderflow,
+    #[msg("Missing pda bump")]
+    MissingBump,
+    #[msg("Price rounds to zero at mint precision")]
+    PriceBelowPrecision,
+    #[msg("Invalid token amount")]
+    InvalidAmount,
+    #[msg("Price below pool floor")]
+    PriceBelowFloor,
+    #[msg("Price above pool ceiling")]
+    PriceAboveCeiling,
+    #[msg("Invalid price bounds")]
+    InvalidPriceBounds,
+    #[msg("Spread must be under 10000 bps")]
+    InvalidSpread,
+    #[msg("Pool expired")]
+    PoolExpired,
+    #[msg("Taker not on pool allowlist")]
+    TakerNotAllowed,
+    #[msg("Wallet buy limit reached")]
+    BuyLimitReached,
+    #[msg("Pool has a buy limit, pass the buyer record")]
+    MissingBuyerRecord,
+    #[msg("Referral bps above pool maximum")]
+    ReferralTooHigh,
+    #[msg("Referral needs a referrer token account")]
+    MissingReferrer,
+    #[msg("Flash loans disabled for pool")]
+    FlashLoansDisabled,
+    #[msg("Pool has an outstanding flash loan")]
+    FlashLoanActive,
+    #[msg("No flash loan for this nft")]
+    NoFlashLoan,
+    #[msg("Flash borrow without a matching repay")]
+    FlashRepayMissing,
+    #[msg("Rentals disabled for pool")]
+    RentalsDisabled,
+    #[msg("Invalid rental duration")]
+    InvalidRentalDuration,
+    #[msg("Rental not expired")]
+    RentalNotExpired,
+    #[msg("Oldest nft not among candidates")]
+    NoCandidate,
+    #[msg("Invalid price multiplier")]
+    InvalidMultiplier,
+    #[msg("Oracle price stale or not set")]
+    StaleOracle,
+    #[msg("Invalid oracle config")]
+    InvalidOracleConfig,
+    #[msg("Bid quantity must be positive")]
+    InvalidQuantity,
+    #[msg("Mint is not an nft")]
+    InvalidNftMint,
+    #[msg("Invalid remaining accounts")]
+    InvalidRemainingAccounts,
+    #[msg("Wrong inventory page")]
+    WrongInventoryPage,
+    #[msg("Previous collection index page has room")]
+    IndexPageNotFull,
+    #[msg("Missing price feed")]
+    MissingPriceFeed,
+    #[msg("Bid needs a collection or merkle root")]
+    BidWithoutFilter,
+    #[msg("Bid expiry already passed")]
+    InvalidBidExpiry,
 }
 // test commit 2
\ No newline at end of file
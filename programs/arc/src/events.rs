@@ -0,0 +1,132 @@
+use anchor_lang::prelude::*;
+
+// events carry the pool's state after the change so indexers can replay logs alone
+
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub collection: Option<Pubkey>,
+    pub curve_type: u8,
+    pub pool_type: u8,
+    pub delta: i64,
+    pub spot_price: u64,
+    pub fee_bps: u64,
+}
+
+#[event]
+pub struct PoolClosed {
+    pub pool: Pubkey,
+}
+
+#[event]
+pub struct NftDeposited {
+    pub pool: Pubkey,
+    pub nft_mint: Pubkey,
+    pub n_nft: u64,
+}
+
+#[event]
+pub struct NftWithdrawn {
+    pub pool: Pubkey,
+    pub nft_mint: Pubkey,
+    pub n_nft: u64,
+}
+
+#[event]
+pub struct TokenDeposited {
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub n_token: u64,
+}
+
+#[event]
+pub struct TokenWithdrawn {
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub n_token: u64,
+}
+
+#[event]
+pub struct Swap {
+    pub pool: Pubkey,
+    pub nft_mint: Pubkey,
+    pub trader: Pubkey,
+    // true when the trader bought the nft from the pool
+    pub buy: bool,
+    pub price: u64,
+    pub pool_fee: u64,
+    pub curator_fee: u64,
+    pub spot_price: u64,
+    pub n_nft: u64,
+    pub n_token: u64,
+}
+
+// referral fee a swap paid to a frontend, alongside its Swap event
+#[event]
+pub struct Referral {
+    pub pool: Pubkey,
+    // token account the fee was paid to
+    pub referrer: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FlashLoan {
+    pub pool: Pubkey,
+    pub nft_mint: Pubkey,
+    pub borrower: Pubkey,
+    pub fee: u64,
+}
+
+#[event]
+pub struct Rented {
+    pub pool: Pubkey,
+    pub nft_mint: Pubkey,
+    pub renter: Pubkey,
+    pub fee: u64,
+    pub expires_at: i64,
+    pub n_nft: u64,
+    pub n_token: u64,
+}
+
+#[event]
+pub struct RentalReturned {
+    pub pool: Pubkey,
+    pub nft_mint: Pubkey,
+    pub n_nft: u64,
+}
+
+// emitted by the read-only quote instruction, sides are None where the pool would refuse the trade
+#[event]
+pub struct Quote {
+    pub pool: Pubkey,
+    pub spot_price: u64,
+    // total a buyer pays for the next nft
+    pub buy: Option<u64>,
+    // net a seller receives for the next nft
+    pub sell: Option<u64>,
+    pub spread_bps: u64,
+    pub multiplier_bps: u64,
+    // see Pool::price_status
+    pub price_status: u8,
+}
+
+#[event]
+pub struct OracleSynced {
+    pub pool: Pubkey,
+    pub price_feed: Pubkey,
+    pub feed_price: u64,
+    pub spot_price: u64,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct MerkleRootSet {
+    pub pool: Pubkey,
+    pub old_root: Option<[u8; 32]>,
+    pub new_root: Option<[u8; 32]>,
+    pub recheck_inventory: bool,
+    pub priced_leaves: bool,
+}
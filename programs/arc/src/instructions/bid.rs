@@ -0,0 +1,241 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token,
+    token::{CloseAccount, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{error::ErrorCode, state::Bid, utils::assert_is_ata};
+
+pub fn place_bid(
+    ctx: Context<PlaceBid>,
+    bid_nonce: u8,
+    price: u64,
+    quantity: u64,
+    expiry: i64,
+    merkle_root: [u8; 32],
+) -> Result<()> {
+    let bid = &mut ctx.accounts.bid;
+    if ctx.accounts.collection.key() != ctx.accounts.system_program.key() {
+        bid.collection = Some(ctx.accounts.collection.key());
+    }
+    if merkle_root != [0u8; 32] {
+        bid.merkle_root = Some(merkle_root);
+    }
+    bid.bidder = ctx.accounts.bidder.key();
+    bid.mint = ctx.accounts.mint.key();
+    bid.price = price;
+    bid.quantity = quantity;
+    bid.expiry = expiry;
+    bid.bid_nonce = bid_nonce;
+    bid.check_terms(Clock::get()?.unix_timestamp)?;
+
+    assert_is_ata(
+        &ctx.accounts.bidder_ata.to_account_info(),
+        ctx.accounts.bidder.key,
+        &bid.mint,
+    )?;
+
+    // escrow the full bid up front so fills never depend on the bidder
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.bidder_ata.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+                authority: ctx.accounts.bidder.to_account_info(),
+            },
+        ),
+        bid.escrow_amount()?,
+    )?;
+
+    Ok(())
+}
+
+pub fn fill_bid(ctx: Context<FillBid>, merkle_proof: Vec<[u8; 32]>) -> Result<()> {
+    let bid_key = ctx.accounts.bid.key();
+    let bid = &mut ctx.accounts.bid;
+    let price = bid.fill(Clock::get()?.unix_timestamp)?;
+    bid.check_nft(
+        &ctx.accounts.nft_mint.to_account_info(),
+        &ctx.accounts.nft_mint_metadata,
+        merkle_proof,
+    )?;
+
+    assert_is_ata(
+        &ctx.accounts.seller_token_ata.to_account_info(),
+        ctx.accounts.seller.key,
+        &bid.mint,
+    )?;
+
+    assert_is_ata(
+        &ctx.accounts.seller_nft_ata.to_account_info(),
+        ctx.accounts.seller.key,
+        &ctx.accounts.nft_mint.key(),
+    )?;
+
+    assert_is_ata(
+        &ctx.accounts.bidder_nft_ata.to_account_info(),
+        &bid.bidder,
+        &ctx.accounts.nft_mint.key(),
+    )?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.seller_nft_ata.to_account_info(),
+                to: ctx.accounts.bidder_nft_ata.to_account_info(),
+                authority: ctx.accounts.seller.to_account_info(),
+            },
+        ),
+        1,
+    )?;
+
+    let bump = *ctx.bumps.get("bid_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"bid_auth", bid_key.as_ref(), &[bump]];
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.escrow.to_account_info(),
+                to: ctx.accounts.seller_token_ata.to_account_info(),
+                authority: ctx.accounts.bid_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+        price,
+    )?;
+
+    Ok(())
+}
+
+pub fn cancel_bid(ctx: Context<CancelBid>) -> Result<()> {
+    let bid_key = ctx.accounts.bid.key();
+
+    assert_is_ata(
+        &ctx.accounts.bidder_ata.to_account_info(),
+        ctx.accounts.bidder.key,
+        &ctx.accounts.bid.mint,
+    )?;
+
+    let bump = *ctx.bumps.get("bid_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"bid_auth", bid_key.as_ref(), &[bump]];
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.escrow.to_account_info(),
+                to: ctx.accounts.bidder_ata.to_account_info(),
+                authority: ctx.accounts.bid_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+        ctx.accounts.escrow.amount,
+    )?;
+
+    token::close_account(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.escrow.to_account_info(),
+                destination: ctx.accounts.bidder.to_account_info(),
+                authority: ctx.accounts.bid_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(bid_nonce: u8)]
+pub struct PlaceBid<'info> {
+    ///CHECK: ?
+    pub collection: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        space = 8 + 155,
+        payer=bidder,
+        seeds=[b"bid", bidder.key().as_ref(), collection.key().as_ref(), mint.key().as_ref(), &[bid_nonce]],
+        bump,
+    )]
+    pub bid: Box<Account<'info, Bid>>,
+
+    #[account(seeds=[b"bid_auth", bid.key().as_ref()], bump)]
+    pub bid_auth: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer=bidder,
+        seeds=[b"bid_escrow", bid.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = bid_auth
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub bidder_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FillBid<'info> {
+    #[account(mut)]
+    pub bid: Box<Account<'info, Bid>>,
+
+    #[account(seeds=[b"bid_auth", bid.key().as_ref()], bump)]
+    pub bid_auth: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"bid_escrow", bid.key().as_ref()], bump)]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    pub nft_mint: Account<'info, Mint>,
+
+    pub nft_mint_metadata: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub bidder_nft_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub seller_nft_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub seller_token_ata: Box<Account<'info, TokenAccount>>,
+
+    pub seller: Signer<'info>,
+
+    // other
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBid<'info> {
+    #[account(mut, has_one = bidder, close = bidder)]
+    pub bid: Box<Account<'info, Bid>>,
+
+    #[account(seeds=[b"bid_auth", bid.key().as_ref()], bump)]
+    pub bid_auth: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"bid_escrow", bid.key().as_ref()], bump)]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub bidder_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
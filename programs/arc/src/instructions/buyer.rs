@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{BuyerRecord, Pool};
+
+// buyers create their record once before buying from a pool with a buy_limit
+pub fn init_buyer_record(ctx: Context<InitBuyerRecord>) -> Result<()> {
+    let record = &mut ctx.accounts.buyer_record;
+    record.pool = ctx.accounts.pool.key();
+    record.buyer = ctx.accounts.buyer.key();
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitBuyerRecord<'info> {
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init,
+        space = BuyerRecord::SPACE,
+        payer=buyer,
+        seeds=[b"buyer_record", pool.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_record: Box<Account<'info, BuyerRecord>>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,70 @@
+use anchor_lang::{prelude::*, AccountsClose};
+use anchor_spl::{
+    token,
+    token::{CloseAccount, Token, TokenAccount},
+};
+
+use crate::{
+    error::ErrorCode,
+    events::PoolClosed,
+    state::{CollectionIndex, Inventory, Pool},
+    utils::assert_keys_equal,
+};
+
+// remaining accounts: the pool's other inventory pages, closed along with it
+pub fn close_pool<'info>(ctx: Context<'_, '_, '_, 'info, ClosePool<'info>>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let pool = &ctx.accounts.pool;
+    if pool.n_nft != 0 || pool.n_rented != 0 || ctx.accounts.token_vault.amount != 0 {
+        return Err(ErrorCode::PoolNotEmpty.into());
+    }
+
+    ctx.accounts.collection_index.deregister(pool_key)?;
+
+    for info in ctx.remaining_accounts.iter() {
+        let page = Account::<Inventory>::try_from(info)?;
+        assert_keys_equal(page.pool, pool_key)?;
+        page.close(ctx.accounts.owner.to_account_info())?;
+    }
+
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
+
+    token::close_account(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.token_vault.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.pool_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+    )?;
+
+    emit!(PoolClosed { pool: pool_key });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(mut, has_one = owner, close = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, has_one = pool, close = owner)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(mut, constraint = pool.is_indexed_in(&collection_index))]
+    pub collection_index: Box<Account<'info, CollectionIndex>>,
+
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
@@ -1,32 +1,173 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{
-    token,
-    token::{Approve, Burn, Mint, MintTo, Token, TokenAccount, Transfer},
-};
-use mpl_token_metadata::state::Metadata;
 
 use crate::error::ErrorCode;
 use crate::{
-    state::Pool,
-    utils::{assert_is_ata, asset_valid_mint},
+    events::MerkleRootSet,
+    state::{CollectionIndex, Pool, PriceFeed, MAX_REFERRAL_BPS},
 };
 
-pub fn edit_delta(ctx: Context<EditDelta>, delta: u64) -> Result<()> {
+pub fn edit_delta(ctx: Context<EditDelta>, delta: i64) -> Result<()> {
+    ctx.accounts.pool.edit_delta(delta)
+}
+
+pub fn edit_spot_price(ctx: Context<EditSpotPrice>, spot_price: u64) -> Result<()> {
+    ctx.accounts.pool.edit_spot_price(spot_price)
+}
+
+pub fn set_price_bounds(
+    ctx: Context<SetPriceBounds>,
+    min_price: u64,
+    max_price: u64,
+) -> Result<()> {
     let pool = &mut ctx.accounts.pool;
+    pool.check_not_shared()?;
 
-    assert_delta(delta, pool.spot_price)?;
+    if max_price != 0 && min_price > max_price {
+        return Err(ErrorCode::InvalidPriceBounds.into());
+    }
 
-    pool.delta = delta;
+    pool.min_price = min_price;
+    pool.max_price = max_price;
+    // the current price must already sit inside the new bounds
+    pool.check_price_bounds(pool.spot_price)?;
 
     Ok(())
 }
 
-pub fn edit_spot_price(ctx: Context<EditSpotPrice>, spot_price: u64) -> Result<()> {
+pub fn set_spread(ctx: Context<SetSpread>, spread_bps: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.check_not_shared()?;
+
+    if pool.pool_type != 2 {
+        return Err(ErrorCode::InvalidPoolType.into());
+    }
+    if spread_bps >= 10000 {
+        return Err(ErrorCode::InvalidSpread.into());
+    }
+
+    pool.spread_bps = spread_bps;
+
+    Ok(())
+}
+
+// 0 clears the expiry, a past timestamp stops trading straight away
+pub fn set_expiry(ctx: Context<SetExpiry>, expires_at: i64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.check_not_shared()?;
+    pool.expires_at = expires_at;
+
+    Ok(())
+}
+
+// None opens the pool to every taker again
+pub fn set_taker_root(ctx: Context<SetTakerRoot>, taker_root: Option<[u8; 32]>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.check_not_shared()?;
+    pool.taker_root = taker_root;
+
+    Ok(())
+}
+
+// 0 lifts the limit, lowering it does not reset what wallets already bought
+pub fn set_buy_limit(ctx: Context<SetBuyLimit>, buy_limit: u64) -> Result<()> {
     let pool = &mut ctx.accounts.pool;
+    pool.check_not_shared()?;
+    pool.buy_limit = buy_limit;
 
-    assert_delta(pool.delta, spot_price)?;
+    Ok(())
+}
+
+// 0 falls back to MAX_REFERRAL_BPS, owners can lower the cap but not raise it
+pub fn set_max_referral_bps(ctx: Context<SetMaxReferralBps>, max_referral_bps: u64) -> Result<()> {
+    if max_referral_bps > MAX_REFERRAL_BPS {
+        return Err(ErrorCode::ReferralTooHigh.into());
+    }
+
+    ctx.accounts.pool.max_referral_bps = max_referral_bps;
 
-    pool.spot_price = spot_price;
+    Ok(())
+}
+
+// None disables flash loans
+pub fn set_flash_fee(ctx: Context<SetFlashFee>, flash_fee: Option<u64>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.check_not_shared()?;
+    pool.flash_fee = flash_fee;
+
+    Ok(())
+}
+
+// fee per rental day, None disables new rentals
+pub fn set_rental_rate(ctx: Context<SetRentalRate>, rental_rate: Option<u64>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.check_not_shared()?;
+    pool.rental_rate = rental_rate;
+
+    Ok(())
+}
+
+// None lifts the nft allowlist. recheck_inventory=false keeps nfts deposited under the old
+// root for sale, true makes every buy prove its nft against the new root.
+// priced_leaves switches the tree to keccak(mint || multiplier_bps) leaves
+pub fn set_merkle_root(
+    ctx: Context<SetMerkleRoot>,
+    merkle_root: Option<[u8; 32]>,
+    recheck_inventory: bool,
+    priced_leaves: bool,
+) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let pool = &mut ctx.accounts.pool;
+    let old_root = pool.merkle_root;
+
+    // lp shares value every nft at the curve price
+    if priced_leaves && pool.lp_mint.is_some() {
+        return Err(ErrorCode::SharedPool.into());
+    }
+
+    pool.merkle_root = merkle_root;
+    pool.recheck_inventory = recheck_inventory;
+    pool.priced_leaves = priced_leaves;
+
+    // pools without a collection are indexed by their root, so they move to the new root's index
+    if ctx.accounts.new_collection_index.key != pool.index_key() {
+        return Err(ErrorCode::CollectionIndexMismatch.into());
+    }
+    if ctx.accounts.collection_index.key() != ctx.accounts.new_collection_index.key() {
+        ctx.accounts.collection_index.deregister(pool_key)?;
+        ctx.accounts
+            .new_collection_index
+            .register(pool_key, pool.spot_price)?;
+        pool.index_page = ctx.accounts.new_collection_index.page;
+    }
+
+    emit!(MerkleRootSet {
+        pool: pool_key,
+        old_root,
+        new_root: merkle_root,
+        recheck_inventory,
+        priced_leaves,
+    });
+
+    Ok(())
+}
+
+// pegs an ORACLE pool to price_feed at oracle_bps of its price, 10000=at the feed.
+// the pool stops trading until the next sync_oracle
+pub fn set_oracle(ctx: Context<SetOracle>, oracle_bps: u64, max_staleness: i64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.check_not_shared()?;
+
+    if pool.curve_type != 4 {
+        return Err(ErrorCode::InvalidCurveType.into());
+    }
+    if oracle_bps == 0 || max_staleness < 0 {
+        return Err(ErrorCode::InvalidOracleConfig.into());
+    }
+
+    pool.oracle = Some(ctx.accounts.price_feed.key());
+    pool.oracle_bps = oracle_bps;
+    pool.max_staleness = max_staleness;
+    pool.oracle_updated_at = 0;
 
     Ok(())
 }
@@ -46,3 +187,92 @@ pub struct EditSpotPrice<'info> {
 
     pub owner: Signer<'info>,
 }
+
+#[derive(Accounts)]
+pub struct SetPriceBounds<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetSpread<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetExpiry<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTakerRoot<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetBuyLimit<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxReferralBps<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFlashFee<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRentalRate<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMerkleRoot<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, constraint = pool.is_indexed_in(&collection_index))]
+    pub collection_index: Box<Account<'info, CollectionIndex>>,
+
+    // the same account as collection_index unless the pool moves to another index page
+    #[account(mut)]
+    pub new_collection_index: Box<Account<'info, CollectionIndex>>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetOracle<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    pub owner: Signer<'info>,
+}
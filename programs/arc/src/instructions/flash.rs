@@ -0,0 +1,137 @@
+use anchor_lang::{prelude::*, solana_program::sysvar::instructions as sysvar_instructions};
+use anchor_spl::{
+    token,
+    token::{Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{
+    error::ErrorCode,
+    events::FlashLoan,
+    state::Pool,
+    utils::{assert_is_ata, assert_repay_follows},
+};
+
+// lends one nft out of its vault, a flash_repay for the same pool, nft and borrower must follow
+// in this transaction
+pub fn flash_borrow(ctx: Context<FlashBorrow>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let nft_mint = ctx.accounts.nft_mint.key();
+    assert_repay_follows(
+        &ctx.accounts.instructions,
+        pool_key,
+        nft_mint,
+        ctx.accounts.borrower.key(),
+    )?;
+    assert_is_ata(
+        &ctx.accounts.borrower_nft_ata.to_account_info(),
+        ctx.accounts.borrower.key,
+        &nft_mint,
+    )?;
+
+    ctx.accounts.pool.lend_nft(nft_mint)?;
+
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.nft_vault.to_account_info(),
+                to: ctx.accounts.borrower_nft_ata.to_account_info(),
+                authority: ctx.accounts.pool_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+        1,
+    )?;
+
+    Ok(())
+}
+
+pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let nft_mint = ctx.accounts.nft_mint.key();
+    let fee = ctx.accounts.pool.repay_nft(nft_mint)?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.borrower_nft_ata.to_account_info(),
+                to: ctx.accounts.nft_vault.to_account_info(),
+                authority: ctx.accounts.borrower.to_account_info(),
+            },
+        ),
+        1,
+    )?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.borrower_token_ata.to_account_info(),
+                to: ctx.accounts.token_vault.to_account_info(),
+                authority: ctx.accounts.borrower.to_account_info(),
+            },
+        ),
+        fee,
+    )?;
+
+    emit!(FlashLoan {
+        pool: pool_key,
+        nft_mint,
+        borrower: ctx.accounts.borrower.key(),
+        fee,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    #[account(mut)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
+    pub nft_vault: Box<Account<'info, TokenAccount>>,
+
+    pub nft_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub borrower_nft_ata: Box<Account<'info, TokenAccount>>,
+
+    pub borrower: Signer<'info>,
+
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// account order is checked by flash_borrow, keep pool first, nft_mint third and borrower seventh
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    #[account(mut)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
+    pub nft_vault: Box<Account<'info, TokenAccount>>,
+
+    pub nft_mint: Account<'info, Mint>,
+
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub borrower_nft_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub borrower_token_ata: Box<Account<'info, TokenAccount>>,
+
+    pub borrower: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::CollectionIndex, utils::assert_keys_equal};
+
+// page 0 opens freely, later pages only once the previous page is full.
+// remaining accounts: the previous page, for page > 0
+pub fn init_collection_index(
+    ctx: Context<InitCollectionIndex>,
+    key: [u8; 32],
+    page: u32,
+) -> Result<()> {
+    if page > 0 {
+        let info = ctx
+            .remaining_accounts
+            .first()
+            .ok_or(ErrorCode::InvalidRemainingAccounts)?;
+        let (prev_key, _) = Pubkey::find_program_address(
+            &[
+                b"collection_index",
+                key.as_ref(),
+                (page - 1).to_le_bytes().as_ref(),
+            ],
+            ctx.program_id,
+        );
+        assert_keys_equal(prev_key, info.key())?;
+        let prev = Account::<CollectionIndex>::try_from(info)?;
+        if !prev.is_full() {
+            return Err(ErrorCode::IndexPageNotFull.into());
+        }
+    }
+
+    let collection_index = &mut ctx.accounts.collection_index;
+    collection_index.key = key;
+    collection_index.page = page;
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(key: [u8; 32], page: u32)]
+pub struct InitCollectionIndex<'info> {
+    #[account(
+        init,
+        space = CollectionIndex::SPACE,
+        payer=payer,
+        seeds=[b"collection_index", key.as_ref(), page.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub collection_index: Box<Account<'info, CollectionIndex>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -4,20 +4,40 @@ use anchor_spl::{
     associated_token::AssociatedToken,
     token::{Mint, Token, TokenAccount},
 };
-use crate::{state::Pool, utils::{asset_pool_type, assert_curve_type, assert_delta}};
+use crate::{error::ErrorCode, events::PoolCreated, state::{CollectionIndex, Inventory, Pool}, utils::{asset_pool_type, assert_curve_type, assert_delta, assert_floor_price, assert_spot_price, assert_xyk_reserves}};
 
 
+// pricing curve a pool starts on, floor_price and decay_rate are dutch only,
+// the virtual reserves xyk only
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct CurveArgs {
+    pub curve_type: u8,
+    pub delta: i64,
+    pub spot_price: u64,
+    pub floor_price: u64,
+    pub decay_rate: u64,
+    pub virtual_token_reserve: u64,
+    pub virtual_nft_reserve: u64,
+}
+
 pub fn handler(
     ctx: Context<InitializePool>, 
     owner_nonce: u8,
-    curve_type: u8,
-    delta: i64,
-    spot_price: u64,
     pool_type: u8,
     fee_bps: u64,
     curator_fee_bps: u64,
-    merkle_root: [u8; 32]
+    merkle_root: [u8; 32],
+    curve: CurveArgs,
 ) -> Result<()> {
+    let CurveArgs {
+        curve_type,
+        delta,
+        spot_price,
+        floor_price,
+        decay_rate,
+        virtual_token_reserve,
+        virtual_nft_reserve,
+    } = curve;
 
     let pool = &mut ctx.accounts.pool;
     if ctx.accounts.collection.key() != ctx.accounts.system_program.key() {
@@ -28,8 +48,12 @@ pub fn handler(
     }
     pool.n_nft = 0;
     pool.n_token = 0;
+    pool.valid = true;
+    ctx.accounts.inventory.pool = pool.key();
+    pool.inventory_pages = 1;
 
     pool.mint = ctx.accounts.mint.key();
+    pool.decimals = ctx.accounts.mint.decimals;
     pool.owner = ctx.accounts.owner.key();
     pool.owner_nonce = owner_nonce;
     pool.fee_bps = fee_bps;
@@ -37,12 +61,41 @@ pub fn handler(
     pool.curator_fee_bps = curator_fee_bps;
     assert_curve_type(curve_type)?;
     pool.curve_type = curve_type;
-    assert_delta(delta, spot_price)?;
+    assert_spot_price(curve_type, spot_price)?;
+    assert_delta(curve_type, delta, spot_price)?;
     pool.delta = delta;
     pool.spot_price = spot_price;
     asset_pool_type(pool_type)?;
     pool.pool_type = pool_type;
+    assert_floor_price(curve_type, floor_price, spot_price)?;
+    pool.floor_price = floor_price;
+    pool.decay_rate = decay_rate;
+    pool.last_update = Clock::get()?.unix_timestamp;
+    assert_xyk_reserves(curve_type, pool_type, virtual_token_reserve, virtual_nft_reserve)?;
+    pool.virtual_token_reserve = virtual_token_reserve;
+    pool.virtual_nft_reserve = virtual_nft_reserve;
+    if curve_type == 3 {
+        pool.spot_price = pool.xyk_spot_price()?;
+    }
 
+    let collection_index = &mut ctx.accounts.collection_index;
+    if collection_index.key != pool.index_key() {
+        return Err(ErrorCode::CollectionIndexMismatch.into());
+    }
+    collection_index.register(pool.key(), pool.spot_price)?;
+    pool.index_page = collection_index.page;
+
+    emit!(PoolCreated {
+        pool: pool.key(),
+        owner: pool.owner,
+        mint: pool.mint,
+        collection: pool.collection,
+        curve_type: pool.curve_type,
+        pool_type: pool.pool_type,
+        delta: pool.delta,
+        spot_price: pool.spot_price,
+        fee_bps: pool.fee_bps,
+    });
     Ok(())
 }
 
@@ -56,14 +109,26 @@ pub struct InitializePool<'info> {
 
     #[account(
         init, 
-        // toodo: fix space
-        space = 214,
+        space = 8 + 515,
         payer=owner, 
         seeds=[b"pool", owner.key().as_ref(), collection.key().as_ref(), mint.key().as_ref(), &[owner_nonce]], 
         bump,
     )]
     pub pool: Box<Account<'info, Pool>>,
 
+    // first page of the mints held by the pool
+    #[account(
+        init,
+        space = Inventory::SPACE,
+        payer=owner,
+        seeds=[b"inventory", pool.key().as_ref(), 0u32.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(mut)]
+    pub collection_index: Box<Account<'info, CollectionIndex>>,
+
     // authority so 1 acc pass in can derive all other pdas 
     #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
     pub pool_auth: AccountInfo<'info>,
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    state::{Inventory, Pool},
+};
+
+// opens the pool's next inventory page once the newest one is full, anyone may pay for it
+pub fn init_inventory_page(ctx: Context<InitInventoryPage>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    if ctx.accounts.tail.page + 1 != pool.inventory_pages {
+        return Err(ErrorCode::WrongInventoryPage.into());
+    }
+    // empty pages would only leave gaps for the head to crank through
+    if !ctx.accounts.tail.is_full() {
+        return Err(ErrorCode::WrongInventoryPage.into());
+    }
+
+    let inventory = &mut ctx.accounts.inventory;
+    inventory.pool = pool.key();
+    inventory.page = pool.inventory_pages;
+    pool.inventory_pages = pool
+        .inventory_pages
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
+// moves the head past a page emptied by removals while it was not the head
+pub fn advance_inventory_head(ctx: Context<AdvanceInventoryHead>) -> Result<()> {
+    if !ctx
+        .accounts
+        .pool
+        .advance_inventory_head(&ctx.accounts.inventory)
+    {
+        return Err(ErrorCode::WrongInventoryPage.into());
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitInventoryPage<'info> {
+    #[account(mut)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(has_one = pool)]
+    pub tail: Box<Account<'info, Inventory>>,
+
+    #[account(
+        init,
+        space = Inventory::SPACE,
+        payer=payer,
+        seeds=[b"inventory", pool.key().as_ref(), pool.inventory_pages.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdvanceInventoryHead<'info> {
+    #[account(mut)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+}
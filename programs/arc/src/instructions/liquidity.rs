@@ -1,46 +1,53 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, system_program};
 use anchor_spl::{
     token,
-    token::{Approve, Mint, Token, TokenAccount, Transfer},
+    token::{CloseAccount, InitializeAccount, Mint, Token, TokenAccount, Transfer},
 };
 
-use crate::{error::ErrorCode, utils::asset_merkle};
 use crate::{
-    state::Pool,
-    utils::{assert_is_ata, asset_collection},
+    error::ErrorCode,
+    events::{NftDeposited, NftWithdrawn, TokenDeposited, TokenWithdrawn},
+};
+use crate::{
+    state::{Inventory, Pool},
+    utils::{assert_is_ata, assert_keys_equal, asset_collection},
 };
 
-pub fn add_nft(ctx: Context<AddNFTLiquidity>, merkle_proof: Vec<[u8; 32]>) -> Result<()> {
-    let pool_info = ctx.accounts.pool.to_account_info();
-    let pool_key = ctx.accounts.pool.key();
+// remaining_accounts per nft added: nft_mint, nft_vault, owner_nft_ata, mint_metadata
+const ADD_ITEM_ACCOUNTS: usize = 4;
+// remaining_accounts per nft removed: nft_mint, nft_vault, owner_nft_ata
+const REMOVE_ITEM_ACCOUNTS: usize = 3;
+
+pub fn add_nft(
+    ctx: Context<AddNFTLiquidity>,
+    merkle_proof: Vec<[u8; 32]>,
+    multiplier_bps: u64,
+) -> Result<()> {
     let pool = &mut ctx.accounts.pool;
     if pool.pool_type == 0 {
         return Err(ErrorCode::InvalidPoolType.into());
     }
+    if pool.lp_mint.is_some() {
+        return Err(ErrorCode::SharedPool.into());
+    }
 
-    if pool.collection.is_some() {
+    if let Some(collection) = pool.collection {
         asset_collection(
             &ctx.accounts.nft_mint.to_account_info(),
             &ctx.accounts.mint_metadata,
-            pool.collection.unwrap(),
+            collection,
         )?;
     }
 
-    if pool.merkle_root.is_some() {
-        asset_merkle(
-            ctx.accounts.nft_mint.key(),
-            pool.merkle_root.unwrap(),
-            merkle_proof,
-        )?;
-    }
+    pool.check_nft_proof(ctx.accounts.nft_mint.key(), multiplier_bps, merkle_proof)?;
 
     assert_is_ata(
-        &ctx.accounts.nft_mint.to_account_info(),
-        &ctx.accounts.owner.key,
-        &pool.mint,
+        &ctx.accounts.owner_ata.to_account_info(),
+        ctx.accounts.owner.key,
+        &ctx.accounts.nft_mint.key(),
     )?;
 
-    assert!(ctx.accounts.nft_vault.amount == 0);
+    require!(ctx.accounts.nft_vault.amount == 0, ErrorCode::VaultNotEmpty);
     token::transfer(
         CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
@@ -53,24 +60,16 @@ pub fn add_nft(ctx: Context<AddNFTLiquidity>, merkle_proof: Vec<[u8; 32]>) -> Re
         1,
     )?;
 
-    // assign delegate for front-end calls of getProgramAccounts
-    let bump = *ctx.bumps.get("pool_auth").unwrap();
-    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
-    token::approve(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Approve {
-                to: ctx.accounts.nft_vault.to_account_info(),
-                delegate: pool_info,
-                authority: ctx.accounts.owner.to_account_info(),
-            },
-        )
-        .with_signer(&[pda_sign]),
-        1,
-    )?;
+    ctx.accounts
+        .inventory
+        .insert(pool, ctx.accounts.nft_mint.key())?;
+    pool.deposit_nft()?;
 
-    pool.n_nft += 1;
-    update_pool_state(pool)?;
+    emit!(NftDeposited {
+        pool: pool.key(),
+        nft_mint: ctx.accounts.nft_mint.key(),
+        n_nft: pool.n_nft,
+    });
     Ok(())
 }
 
@@ -80,6 +79,9 @@ pub fn remove_nft(ctx: Context<RemoveNFTLiquidity>) -> Result<()> {
     if pool.pool_type == 0 {
         return Err(ErrorCode::InvalidPoolType.into());
     }
+    if pool.lp_mint.is_some() {
+        return Err(ErrorCode::SharedPool.into());
+    }
 
     assert_is_ata(
         &ctx.accounts.owner_ata.to_account_info(),
@@ -87,7 +89,7 @@ pub fn remove_nft(ctx: Context<RemoveNFTLiquidity>) -> Result<()> {
         &ctx.accounts.nft_mint.key(),
     )?;
 
-    let bump = *ctx.bumps.get("pool_auth").unwrap();
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
     let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
 
     token::transfer(
@@ -103,26 +105,220 @@ pub fn remove_nft(ctx: Context<RemoveNFTLiquidity>) -> Result<()> {
         1,
     )?;
 
-    pool.n_nft -= 1;
-    update_pool_state(pool)?;
+    ctx.accounts
+        .inventory
+        .remove(pool, ctx.accounts.nft_mint.key())?;
+    pool.withdraw_nft()?;
+
+    emit!(NftWithdrawn {
+        pool: pool_key,
+        nft_mint: ctx.accounts.nft_mint.key(),
+        n_nft: pool.n_nft,
+    });
+
+    Ok(())
+}
+
+// add_nft for many mints at once, vaults that do not exist yet are created here
+pub fn add_nfts<'info>(
+    ctx: Context<'_, '_, '_, 'info, AddNFTs<'info>>,
+    merkle_proofs: Vec<Vec<[u8; 32]>>,
+    multipliers_bps: Vec<u64>,
+) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let pool = &mut ctx.accounts.pool;
+    if pool.pool_type == 0 {
+        return Err(ErrorCode::InvalidPoolType.into());
+    }
+    if pool.lp_mint.is_some() {
+        return Err(ErrorCode::SharedPool.into());
+    }
+    if ctx.remaining_accounts.is_empty() {
+        return Err(ErrorCode::InvalidRemainingAccounts.into());
+    }
+
+    let mut n_nft = pool.n_nft;
+    let mut deposited = Vec::new();
+    for (item, chunk) in ctx.remaining_accounts.chunks(ADD_ITEM_ACCOUNTS).enumerate() {
+        if chunk.len() != ADD_ITEM_ACCOUNTS {
+            return Err(ErrorCode::InvalidRemainingAccounts.into());
+        }
+        let nft_mint = &chunk[0];
+        let nft_vault = &chunk[1];
+        let owner_nft_ata = &chunk[2];
+        let mint_metadata = &chunk[3];
+
+        if let Some(collection) = pool.collection {
+            asset_collection(nft_mint, mint_metadata, collection)?;
+        }
+        let merkle_proof = merkle_proofs.get(item).cloned().unwrap_or_default();
+        let multiplier_bps = multipliers_bps.get(item).copied().unwrap_or_default();
+        pool.check_nft_proof(nft_mint.key(), multiplier_bps, merkle_proof)?;
+        assert_is_ata(owner_nft_ata, ctx.accounts.owner.key, nft_mint.key)?;
+
+        let (vault_key, vault_bump) = Pubkey::find_program_address(
+            &[b"vault", pool_key.as_ref(), nft_mint.key().as_ref()],
+            ctx.program_id,
+        );
+        assert_keys_equal(vault_key, nft_vault.key())?;
+        if nft_vault.data_is_empty() {
+            let vault_sign = &[
+                b"vault",
+                pool_key.as_ref(),
+                nft_mint.key.as_ref(),
+                &[vault_bump],
+            ];
+            system_program::create_account(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::CreateAccount {
+                        from: ctx.accounts.owner.to_account_info(),
+                        to: nft_vault.clone(),
+                    },
+                )
+                .with_signer(&[vault_sign]),
+                ctx.accounts.rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                &token::ID,
+            )?;
+            token::initialize_account(CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                InitializeAccount {
+                    account: nft_vault.clone(),
+                    mint: nft_mint.clone(),
+                    authority: ctx.accounts.pool_auth.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+            ))?;
+        } else {
+            let vault = Account::<TokenAccount>::try_from(nft_vault)?;
+            assert_keys_equal(vault.owner, ctx.accounts.pool_auth.key())?;
+            require!(vault.amount == 0, ErrorCode::VaultNotEmpty);
+        }
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: owner_nft_ata.clone(),
+                    to: nft_vault.clone(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        ctx.accounts.inventory.insert(pool, nft_mint.key())?;
+        n_nft = n_nft.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        deposited.push((nft_mint.key(), n_nft));
+    }
+
+    pool.deposit_nfts(deposited.len() as u64)?;
+
+    for (nft_mint, n_nft) in deposited {
+        emit!(NftDeposited {
+            pool: pool_key,
+            nft_mint,
+            n_nft,
+        });
+    }
+    Ok(())
+}
+
+// remove_nft for many mints at once, emptied vaults are closed back to the owner
+pub fn remove_nfts<'info>(ctx: Context<'_, '_, '_, 'info, RemoveNFTs<'info>>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let pool = &mut ctx.accounts.pool;
+    if pool.pool_type == 0 {
+        return Err(ErrorCode::InvalidPoolType.into());
+    }
+    if pool.lp_mint.is_some() {
+        return Err(ErrorCode::SharedPool.into());
+    }
+    if ctx.remaining_accounts.is_empty() {
+        return Err(ErrorCode::InvalidRemainingAccounts.into());
+    }
+
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
+
+    let mut n_nft = pool.n_nft;
+    let mut withdrawn = Vec::new();
+    for chunk in ctx.remaining_accounts.chunks(REMOVE_ITEM_ACCOUNTS) {
+        if chunk.len() != REMOVE_ITEM_ACCOUNTS {
+            return Err(ErrorCode::InvalidRemainingAccounts.into());
+        }
+        let nft_mint = &chunk[0];
+        let nft_vault = &chunk[1];
+        let owner_nft_ata = &chunk[2];
+
+        assert_is_ata(owner_nft_ata, ctx.accounts.owner.key, nft_mint.key)?;
+        let (vault_key, _) = Pubkey::find_program_address(
+            &[b"vault", pool_key.as_ref(), nft_mint.key().as_ref()],
+            ctx.program_id,
+        );
+        assert_keys_equal(vault_key, nft_vault.key())?;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: nft_vault.clone(),
+                    to: owner_nft_ata.clone(),
+                    authority: ctx.accounts.pool_auth.to_account_info(),
+                },
+            )
+            .with_signer(&[pda_sign]),
+            1,
+        )?;
+        token::close_account(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: nft_vault.clone(),
+                    destination: ctx.accounts.owner.to_account_info(),
+                    authority: ctx.accounts.pool_auth.to_account_info(),
+                },
+            )
+            .with_signer(&[pda_sign]),
+        )?;
+
+        ctx.accounts.inventory.remove(pool, nft_mint.key())?;
+        n_nft = n_nft.checked_sub(1).ok_or(ErrorCode::InventoryUnderflow)?;
+        withdrawn.push((nft_mint.key(), n_nft));
+    }
+
+    pool.withdraw_nfts(withdrawn.len() as u64)?;
 
+    for (nft_mint, n_nft) in withdrawn {
+        emit!(NftWithdrawn {
+            pool: pool_key,
+            nft_mint,
+            n_nft,
+        });
+    }
     Ok(())
 }
 
 pub fn add_token_liquidity(ctx: Context<AddTokenLiquidity>, amount: u64) -> Result<()> {
-    let pool_info = ctx.accounts.pool.to_account_info();
     let pool_key = ctx.accounts.pool.key();
     let pool = &mut ctx.accounts.pool;
     if pool.pool_type == 1 {
         return Err(ErrorCode::InvalidPoolType.into());
     }
+    if pool.lp_mint.is_some() {
+        return Err(ErrorCode::SharedPool.into());
+    }
 
     assert_is_ata(
-        &ctx.accounts.token_mint.to_account_info(),
-        &ctx.accounts.owner.key,
+        &ctx.accounts.owner_ata.to_account_info(),
+        ctx.accounts.owner.key,
         &pool.mint,
     )?;
 
+    // tokens sent straight to the vault become principal
+    pool.sync_n_token(ctx.accounts.token_vault.amount)?;
+
     token::transfer(
         CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
@@ -132,27 +328,16 @@ pub fn add_token_liquidity(ctx: Context<AddTokenLiquidity>, amount: u64) -> Resu
                 authority: ctx.accounts.owner.to_account_info(),
             },
         ),
-        1,
-    )?;
-
-    // assign delegate for front-end calls of getProgramAccounts
-    let bump = *ctx.bumps.get("pool_auth").unwrap();
-    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
-    token::approve(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Approve {
-                to: ctx.accounts.token_vault.to_account_info(),
-                delegate: pool_info,
-                authority: ctx.accounts.owner.to_account_info(),
-            },
-        )
-        .with_signer(&[pda_sign]),
         amount,
     )?;
 
-    pool.n_token = ctx.accounts.token_vault.amount;
-    update_pool_state(pool)?;
+    pool.deposit_tokens(amount)?;
+
+    emit!(TokenDeposited {
+        pool: pool_key,
+        amount,
+        n_token: pool.n_token,
+    });
 
     Ok(())
 }
@@ -163,6 +348,9 @@ pub fn remove_token_liquidity(ctx: Context<RemoveTokenLiquidity>, amount: u64) -
     if pool.pool_type == 1 {
         return Err(ErrorCode::InvalidPoolType.into());
     }
+    if pool.lp_mint.is_some() {
+        return Err(ErrorCode::SharedPool.into());
+    }
 
     assert_is_ata(
         &ctx.accounts.owner_ata.to_account_info(),
@@ -170,7 +358,9 @@ pub fn remove_token_liquidity(ctx: Context<RemoveTokenLiquidity>, amount: u64) -
         &ctx.accounts.token_mint.key(),
     )?;
 
-    let bump = *ctx.bumps.get("pool_auth").unwrap();
+    pool.withdraw_tokens(amount)?;
+
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
     let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
 
     token::transfer(
@@ -186,23 +376,48 @@ pub fn remove_token_liquidity(ctx: Context<RemoveTokenLiquidity>, amount: u64) -
         amount,
     )?;
 
-    pool.n_token = ctx.accounts.token_vault.amount;
-    update_pool_state(pool)?;
+    emit!(TokenWithdrawn {
+        pool: pool_key,
+        amount,
+        n_token: pool.n_token,
+    });
 
     Ok(())
 }
 
-pub fn update_pool_state(pool: &mut Box<Account<Pool>>) -> Result<()> {
+pub fn withdraw_fees(ctx: Context<WithdrawFees>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let pool = &mut ctx.accounts.pool;
     if pool.pool_type != 2 {
-        return Ok(());
-    }
-    // todo: dubious
-    if pool.n_token < pool.spot_price * pool.n_nft {
-        pool.valid = false;
+        return Err(ErrorCode::InvalidPoolType.into());
     }
-    if pool.n_nft < pool.n_token / pool.spot_price {
-        pool.valid = false;
+    if pool.lp_mint.is_some() {
+        return Err(ErrorCode::SharedPool.into());
     }
+
+    assert_is_ata(
+        &ctx.accounts.owner_ata.to_account_info(),
+        ctx.accounts.owner.key,
+        &pool.mint,
+    )?;
+
+    let fees = pool.take_fees();
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.token_vault.to_account_info(),
+                to: ctx.accounts.owner_ata.to_account_info(),
+                authority: ctx.accounts.pool_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+        fees,
+    )?;
+
     Ok(())
 }
 
@@ -211,10 +426,13 @@ pub struct AddNFTLiquidity<'info> {
     #[account(mut, has_one = owner)]
     pub pool: Box<Account<'info, Pool>>,
 
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
     #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
     pub pool_auth: AccountInfo<'info>,
 
-    #[account(seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
+    #[account(mut, seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
     pub nft_vault: Box<Account<'info, TokenAccount>>,
 
     pub nft_mint: Account<'info, Mint>,
@@ -235,6 +453,9 @@ pub struct RemoveNFTLiquidity<'info> {
     #[account(mut, has_one = owner)]
     pub pool: Box<Account<'info, Pool>>,
 
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
     #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
     pub pool_auth: AccountInfo<'info>,
 
@@ -251,6 +472,45 @@ pub struct RemoveNFTLiquidity<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct AddNFTs<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    // pays for any vaults created
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    // other
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveNFTs<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    // receives the rent of the closed vaults
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct AddTokenLiquidity<'info> {
     #[account(mut, has_one = owner)]
@@ -259,7 +519,7 @@ pub struct AddTokenLiquidity<'info> {
     #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
     pub pool_auth: AccountInfo<'info>,
 
-    #[account(seeds=[b"vault", pool.key().as_ref(), token_mint.key().as_ref()], bump)]
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
     pub token_vault: Box<Account<'info, TokenAccount>>,
 
     pub token_mint: Account<'info, Mint>,
@@ -281,7 +541,7 @@ pub struct RemoveTokenLiquidity<'info> {
     #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
     pub pool_auth: AccountInfo<'info>,
 
-    #[account(mut,seeds=[b"vault", pool.key().as_ref(), token_mint.key().as_ref()], bump,close=token_vault)]
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
     pub token_vault: Box<Account<'info, TokenAccount>>,
 
     pub token_mint: Account<'info, Mint>,
@@ -295,9 +555,20 @@ pub struct RemoveTokenLiquidity<'info> {
 }
 
 #[derive(Accounts)]
-pub struct EditDelta<'info> {
+pub struct WithdrawFees<'info> {
     #[account(mut, has_one = owner)]
     pub pool: Box<Account<'info, Pool>>,
 
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, has_one = owner)]
+    pub owner_ata: Box<Account<'info, TokenAccount>>,
+
     pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
 }
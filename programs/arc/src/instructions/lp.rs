@@ -0,0 +1,358 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token,
+    token::{Burn, Mint, MintTo, Token, TokenAccount, Transfer},
+};
+
+use crate::{
+    error::ErrorCode,
+    events::{NftDeposited, NftWithdrawn, TokenDeposited, TokenWithdrawn},
+    state::{Inventory, Pool},
+    utils::{assert_is_ata, assert_keys_equal, asset_collection, asset_merkle},
+};
+
+// lp tokens minted for the first nft deposited, 1.0 lp per nft at 6 decimals
+pub const LP_PER_NFT: u64 = 1_000_000;
+
+pub fn init_lp_mint(ctx: Context<InitLpMint>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    if pool.pool_type != 2 {
+        return Err(ErrorCode::InvalidPoolType.into());
+    }
+    // existing inventory would belong to nobody once shares exist
+    if pool.n_nft != 0 || pool.n_rented != 0 || pool.n_token != 0 || pool.accrued_fees != 0 {
+        return Err(ErrorCode::PoolNotEmpty.into());
+    }
+    // shares value every nft at the curve price, so per-nft multipliers cannot be pooled
+    if pool.priced_leaves {
+        return Err(ErrorCode::InvalidPoolType.into());
+    }
+
+    pool.lp_mint = Some(ctx.accounts.lp_mint.key());
+    Ok(())
+}
+
+pub fn add_shared_liquidity(
+    ctx: Context<AddSharedLiquidity>,
+    max_token_amount: u64,
+    merkle_proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let pool = &mut ctx.accounts.pool;
+
+    if let Some(collection) = pool.collection {
+        asset_collection(
+            &ctx.accounts.nft_mint.to_account_info(),
+            &ctx.accounts.nft_mint_metadata,
+            collection,
+        )?;
+    }
+
+    if let Some(merkle_root) = pool.merkle_root {
+        asset_merkle(ctx.accounts.nft_mint.key(), merkle_root, merkle_proof)?;
+    }
+
+    assert_is_ata(
+        &ctx.accounts.depositor_nft_ata.to_account_info(),
+        ctx.accounts.depositor.key,
+        &ctx.accounts.nft_mint.key(),
+    )?;
+
+    assert_is_ata(
+        &ctx.accounts.depositor_token_ata.to_account_info(),
+        ctx.accounts.depositor.key,
+        &pool.mint,
+    )?;
+
+    // one nft buys 1/n_nft of the pool, matched by the same share of its tokens
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    let (lp_out, token_amount) = if lp_supply == 0 {
+        (LP_PER_NFT, max_token_amount)
+    } else {
+        if pool.n_nft == 0 {
+            return Err(ErrorCode::NotEnoughOut.into());
+        }
+        let lp_out = lp_supply / pool.n_nft;
+        let pool_tokens = ctx.accounts.token_vault.amount as u128;
+        let token_amount = (pool_tokens * lp_out as u128).div_ceil(lp_supply as u128);
+        (
+            lp_out,
+            u64::try_from(token_amount).map_err(|_| ErrorCode::MathOverflow)?,
+        )
+    };
+    if lp_out == 0 {
+        return Err(ErrorCode::NoPoolMintOutput.into());
+    }
+    if token_amount > max_token_amount {
+        return Err(ErrorCode::SlippageExceeded.into());
+    }
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.depositor_nft_ata.to_account_info(),
+                to: ctx.accounts.nft_vault.to_account_info(),
+                authority: ctx.accounts.depositor.to_account_info(),
+            },
+        ),
+        1,
+    )?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.depositor_token_ata.to_account_info(),
+                to: ctx.accounts.token_vault.to_account_info(),
+                authority: ctx.accounts.depositor.to_account_info(),
+            },
+        ),
+        token_amount,
+    )?;
+
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
+
+    token::mint_to(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                to: ctx.accounts.depositor_lp_ata.to_account_info(),
+                authority: ctx.accounts.pool_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+        lp_out,
+    )?;
+
+    ctx.accounts
+        .inventory
+        .insert(pool, ctx.accounts.nft_mint.key())?;
+    pool.deposit_nft()?;
+    pool.deposit_tokens(token_amount)?;
+
+    emit!(NftDeposited {
+        pool: pool_key,
+        nft_mint: ctx.accounts.nft_mint.key(),
+        n_nft: pool.n_nft,
+    });
+    emit!(TokenDeposited {
+        pool: pool_key,
+        amount: token_amount,
+        n_token: pool.n_token,
+    });
+    Ok(())
+}
+
+pub fn remove_shared_liquidity<'info>(
+    ctx: Context<'_, '_, '_, 'info, RemoveSharedLiquidity<'info>>,
+    lp_amount: u64,
+) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let pool = &mut ctx.accounts.pool;
+
+    if lp_amount == 0 || lp_amount > ctx.accounts.withdrawer_lp_ata.amount {
+        return Err(ErrorCode::BurnTooMuch.into());
+    }
+
+    assert_is_ata(
+        &ctx.accounts.withdrawer_token_ata.to_account_info(),
+        ctx.accounts.withdrawer.key,
+        &pool.mint,
+    )?;
+
+    // whole nfts are paid out in kind, the fractional nft at the pool's sell price
+    let lp_supply = ctx.accounts.lp_mint.supply as u128;
+    let lp_amount_u128 = lp_amount as u128;
+    let nft_share = pool.n_nft as u128 * lp_amount_u128;
+    let nft_out = (nft_share / lp_supply) as u64;
+    let nft_remainder_value = (nft_share % lp_supply) * pool.sell_price()? as u128 / lp_supply;
+    let principal_out = (pool.n_token as u128 * lp_amount_u128 / lp_supply + nft_remainder_value)
+        .min(pool.n_token as u128) as u64;
+    let fees_out = (pool.accrued_fees as u128 * lp_amount_u128 / lp_supply) as u64;
+
+    if ctx.remaining_accounts.len() != nft_out as usize * 2 {
+        return Err(ErrorCode::InvalidRemainingAccounts.into());
+    }
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                from: ctx.accounts.withdrawer_lp_ata.to_account_info(),
+                authority: ctx.accounts.withdrawer.to_account_info(),
+            },
+        ),
+        lp_amount,
+    )?;
+
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
+
+    // remaining_accounts: (nft_vault, withdrawer_nft_ata) per nft paid out,
+    // in inventory order so every withdrawer takes the oldest nfts
+    for chunk in ctx.remaining_accounts.chunks(2) {
+        let nft_mint = ctx.accounts.inventory.oldest(pool)?;
+        let (vault_key, _) = Pubkey::find_program_address(
+            &[b"vault", pool_key.as_ref(), nft_mint.as_ref()],
+            ctx.program_id,
+        );
+        assert_keys_equal(vault_key, chunk[0].key())?;
+        assert_is_ata(&chunk[1], ctx.accounts.withdrawer.key, &nft_mint)?;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: chunk[0].clone(),
+                    to: chunk[1].clone(),
+                    authority: ctx.accounts.pool_auth.to_account_info(),
+                },
+            )
+            .with_signer(&[pda_sign]),
+            1,
+        )?;
+        ctx.accounts.inventory.remove(pool, nft_mint)?;
+        pool.withdraw_nft()?;
+
+        emit!(NftWithdrawn {
+            pool: pool_key,
+            nft_mint,
+            n_nft: pool.n_nft,
+        });
+    }
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.token_vault.to_account_info(),
+                to: ctx.accounts.withdrawer_token_ata.to_account_info(),
+                authority: ctx.accounts.pool_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+        principal_out
+            .checked_add(fees_out)
+            .ok_or(ErrorCode::MathOverflow)?,
+    )?;
+
+    pool.accrued_fees = pool
+        .accrued_fees
+        .checked_sub(fees_out)
+        .ok_or(ErrorCode::MathOverflow)?;
+    pool.withdraw_tokens(principal_out)?;
+
+    emit!(TokenWithdrawn {
+        pool: pool_key,
+        amount: principal_out,
+        n_token: pool.n_token,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitLpMint<'info> {
+    #[account(mut, has_one = owner)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer=owner,
+        seeds=[b"lp_mint", pool.key().as_ref()],
+        bump,
+        mint::decimals = 6,
+        mint::authority = pool_auth
+    )]
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AddSharedLiquidity<'info> {
+    #[account(mut, constraint = pool.lp_mint == Some(lp_mint.key()))]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds=[b"lp_mint", pool.key().as_ref()], bump)]
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer=depositor,
+        seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()],
+        bump,
+        token::mint = nft_mint,
+        token::authority = pool_auth
+    )]
+    pub nft_vault: Box<Account<'info, TokenAccount>>,
+
+    pub nft_mint: Account<'info, Mint>,
+
+    pub nft_mint_metadata: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub depositor_nft_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub depositor_token_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = lp_mint, token::authority = depositor)]
+    pub depositor_lp_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveSharedLiquidity<'info> {
+    #[account(mut, constraint = pool.lp_mint == Some(lp_mint.key()))]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, seeds=[b"lp_mint", pool.key().as_ref()], bump)]
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, token::mint = lp_mint, token::authority = withdrawer)]
+    pub withdrawer_lp_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub withdrawer_token_ata: Box<Account<'info, TokenAccount>>,
+
+    pub withdrawer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
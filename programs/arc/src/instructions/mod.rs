@@ -1,8 +1,44 @@
 pub mod init_pool;
 pub use init_pool::*;
 
+pub mod close_pool;
+pub use close_pool::*;
+
+pub mod index;
+pub use index::*;
+
+pub mod inventory;
+pub use inventory::*;
+
 pub mod liquidity;
 pub use liquidity::*;
 
+pub mod edit;
+pub use edit::*;
+
+pub mod quote;
+pub use quote::*;
+
 pub mod swap;
 pub use swap::*;
+
+pub mod route;
+pub use route::*;
+
+pub mod bid;
+pub use bid::*;
+
+pub mod buyer;
+pub use buyer::*;
+
+pub mod flash;
+pub use flash::*;
+
+pub mod rental;
+pub use rental::*;
+
+pub mod oracle;
+pub use oracle::*;
+
+pub mod lp;
+pub use lp::*;
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    events::OracleSynced,
+    state::{CollectionIndex, Pool, PriceFeed},
+};
+
+pub fn init_price_feed(ctx: Context<InitPriceFeed>, price: u64) -> Result<()> {
+    let price_feed = &mut ctx.accounts.price_feed;
+    price_feed.authority = ctx.accounts.authority.key();
+    price_feed.price = price;
+    price_feed.updated_at = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+pub fn update_price_feed(ctx: Context<UpdatePriceFeed>, price: u64) -> Result<()> {
+    let price_feed = &mut ctx.accounts.price_feed;
+    price_feed.price = price;
+    price_feed.updated_at = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+// permissionless, takers put it ahead of their swap in the same transaction
+pub fn sync_oracle(ctx: Context<SyncOracle>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let price_feed = &ctx.accounts.price_feed;
+    let pool = &mut ctx.accounts.pool;
+    if pool.oracle != Some(price_feed.key()) {
+        return Err(ErrorCode::PublicKeyMismatch.into());
+    }
+
+    pool.sync_oracle(price_feed.price, price_feed.updated_at)?;
+    ctx.accounts
+        .collection_index
+        .update_spot_price(pool_key, pool.spot_price);
+
+    emit!(OracleSynced {
+        pool: pool_key,
+        price_feed: price_feed.key(),
+        feed_price: price_feed.price,
+        spot_price: pool.spot_price,
+        updated_at: price_feed.updated_at,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitPriceFeed<'info> {
+    #[account(init, space = PriceFeed::SPACE, payer = authority)]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceFeed<'info> {
+    #[account(mut, has_one = authority)]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncOracle<'info> {
+    #[account(mut)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(mut, constraint = pool.is_indexed_in(&collection_index))]
+    pub collection_index: Box<Account<'info, CollectionIndex>>,
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::{events::Quote, state::Pool};
+
+// read-only, clients simulate it and read both sides of the pool from the Quote event.
+// multiplier_bps prices an nft of a priced-leaf pool, BASE_MULTIPLIER_BPS quotes the curve.
+// remaining accounts: the pool's PriceFeed for ORACLE pools
+pub fn quote(ctx: Context<QuotePool>, multiplier_bps: u64) -> Result<()> {
+    let mut pool = Pool::clone(&ctx.accounts.pool);
+    let now = Clock::get()?.unix_timestamp;
+    pool.sync_price_feed(&mut ctx.remaining_accounts.iter())?;
+    pool.apply_decay(now);
+
+    let (buy, sell) = if pool.is_expired(now) || pool.check_oracle(now).is_err() {
+        (None, None)
+    } else {
+        pool.quote_at(multiplier_bps)
+    };
+    emit!(Quote {
+        pool: ctx.accounts.pool.key(),
+        spot_price: pool.spot_price,
+        buy: buy.map(|fill| fill.cost()).transpose()?,
+        sell: sell.map(|fill| fill.proceeds()).transpose()?,
+        spread_bps: pool.spread_bps,
+        multiplier_bps,
+        price_status: pool.price_status(),
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct QuotePool<'info> {
+    pub pool: Box<Account<'info, Pool>>,
+}
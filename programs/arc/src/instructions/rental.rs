@@ -0,0 +1,262 @@
+use anchor_lang::{prelude::*, solana_program::program::invoke_signed};
+use anchor_spl::{
+    token,
+    token::{Approve, Mint, Token, TokenAccount, Transfer},
+};
+use mpl_token_metadata::instruction::{freeze_delegated_account, thaw_delegated_account};
+
+use crate::{
+    error::ErrorCode,
+    events::{RentalReturned, Rented},
+    state::{Inventory, Pool, Rental},
+    utils::assert_is_ata,
+};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+// the nft goes to the renter's ata, frozen there with pool_auth as delegate until it is returned
+pub fn rent_nft(ctx: Context<RentNFT>, days: u64) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let nft_mint = ctx.accounts.nft_mint.key();
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    pool.check_expiry(now)?;
+
+    assert_is_ata(
+        &ctx.accounts.renter_nft_ata.to_account_info(),
+        ctx.accounts.renter.key,
+        &nft_mint,
+    )?;
+    assert_is_ata(
+        &ctx.accounts.renter_token_ata.to_account_info(),
+        ctx.accounts.renter.key,
+        &pool.mint,
+    )?;
+
+    let fee = pool.rent_nft(days)?;
+    let expires_at = i64::try_from(days)
+        .ok()
+        .and_then(|days| days.checked_mul(SECONDS_PER_DAY))
+        .and_then(|duration| now.checked_add(duration))
+        .ok_or(ErrorCode::InvalidRentalDuration)?;
+
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.nft_vault.to_account_info(),
+                to: ctx.accounts.renter_nft_ata.to_account_info(),
+                authority: ctx.accounts.pool_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+        1,
+    )?;
+
+    token::approve(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Approve {
+                to: ctx.accounts.renter_nft_ata.to_account_info(),
+                delegate: ctx.accounts.pool_auth.to_account_info(),
+                authority: ctx.accounts.renter.to_account_info(),
+            },
+        ),
+        1,
+    )?;
+
+    invoke_signed(
+        &freeze_delegated_account(
+            mpl_token_metadata::id(),
+            ctx.accounts.pool_auth.key(),
+            ctx.accounts.renter_nft_ata.key(),
+            ctx.accounts.nft_edition.key(),
+            nft_mint,
+        ),
+        &[
+            ctx.accounts.pool_auth.to_account_info(),
+            ctx.accounts.renter_nft_ata.to_account_info(),
+            ctx.accounts.nft_edition.to_account_info(),
+            ctx.accounts.nft_mint.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+        ],
+        &[pda_sign],
+    )?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.renter_token_ata.to_account_info(),
+                to: ctx.accounts.token_vault.to_account_info(),
+                authority: ctx.accounts.renter.to_account_info(),
+            },
+        ),
+        fee,
+    )?;
+
+    ctx.accounts.inventory.remove(pool, nft_mint)?;
+
+    let rental = &mut ctx.accounts.rental;
+    rental.pool = pool_key;
+    rental.nft_mint = nft_mint;
+    rental.renter = ctx.accounts.renter.key();
+    rental.renter_nft_ata = ctx.accounts.renter_nft_ata.key();
+    rental.expires_at = expires_at;
+
+    emit!(Rented {
+        pool: pool_key,
+        nft_mint,
+        renter: ctx.accounts.renter.key(),
+        fee,
+        expires_at,
+        n_nft: pool.n_nft,
+        n_token: pool.n_token,
+    });
+    Ok(())
+}
+
+// anyone can crank an expired rental back into the pool vault, rent goes back to the renter
+pub fn return_rental(ctx: Context<ReturnRental>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let nft_mint = ctx.accounts.nft_mint.key();
+    if Clock::get()?.unix_timestamp < ctx.accounts.rental.expires_at {
+        return Err(ErrorCode::RentalNotExpired.into());
+    }
+
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
+
+    invoke_signed(
+        &thaw_delegated_account(
+            mpl_token_metadata::id(),
+            ctx.accounts.pool_auth.key(),
+            ctx.accounts.renter_nft_ata.key(),
+            ctx.accounts.nft_edition.key(),
+            nft_mint,
+        ),
+        &[
+            ctx.accounts.pool_auth.to_account_info(),
+            ctx.accounts.renter_nft_ata.to_account_info(),
+            ctx.accounts.nft_edition.to_account_info(),
+            ctx.accounts.nft_mint.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+        ],
+        &[pda_sign],
+    )?;
+
+    // pool_auth moves it back as delegate, which also clears the delegation
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.renter_nft_ata.to_account_info(),
+                to: ctx.accounts.nft_vault.to_account_info(),
+                authority: ctx.accounts.pool_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+        1,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.return_nft()?;
+    ctx.accounts.inventory.insert(pool, nft_mint)?;
+
+    emit!(RentalReturned {
+        pool: pool_key,
+        nft_mint,
+        n_nft: pool.n_nft,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RentNFT<'info> {
+    #[account(mut)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
+    pub nft_vault: Box<Account<'info, TokenAccount>>,
+
+    pub nft_mint: Account<'info, Mint>,
+
+    // master edition, checked by token metadata when freezing
+    pub nft_edition: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        space = Rental::SPACE,
+        payer=renter,
+        seeds=[b"rental", pool.key().as_ref(), nft_mint.key().as_ref()],
+        bump,
+    )]
+    pub rental: Box<Account<'info, Rental>>,
+
+    #[account(mut)]
+    pub renter_nft_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub renter_token_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub renter: Signer<'info>,
+
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReturnRental<'info> {
+    #[account(mut)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
+    pub nft_vault: Box<Account<'info, TokenAccount>>,
+
+    pub nft_mint: Account<'info, Mint>,
+
+    pub nft_edition: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds=[b"rental", pool.key().as_ref(), nft_mint.key().as_ref()],
+        bump,
+        has_one = renter,
+        has_one = renter_nft_ata,
+        close = renter,
+    )]
+    pub rental: Box<Account<'info, Rental>>,
+
+    #[account(mut)]
+    pub renter_nft_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub renter: AccountInfo<'info>,
+
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
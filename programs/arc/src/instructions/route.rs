@@ -0,0 +1,431 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token,
+    token::{Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{
+    error::ErrorCode,
+    events::Swap,
+    state::{CollectionIndex, Inventory, Pool},
+    utils::{assert_is_ata, assert_keys_equal, asset_collection, asset_merkle},
+};
+
+// remaining_accounts per pool: pool, pool_auth, token_vault, inventory page.
+// sells go on the pool's newest page, buys come off the page that was passed.
+// the PriceFeed of each ORACLE pool follows the pools, in pool order, before any items
+const POOL_ACCOUNTS: usize = 4;
+// remaining_accounts per buy candidate: nft_vault, authority_nft_ata
+const BUY_ITEM_ACCOUNTS: usize = 2;
+// remaining_accounts per sell item: nft_mint, nft_mint_metadata, authority_nft_ata, then one nft vault per pool
+const SELL_ITEM_ACCOUNTS: usize = 3;
+
+struct RoutePool<'info> {
+    pool: Account<'info, Pool>,
+    pool_auth: AccountInfo<'info>,
+    pool_auth_bump: u8,
+    token_vault: Account<'info, TokenAccount>,
+    inventory: Account<'info, Inventory>,
+    // expired pools, and oracle pools with a stale price, stay in the route but are never filled
+    expired: bool,
+}
+
+pub fn route_buy<'info>(
+    ctx: Context<'_, '_, '_, 'info, RouteBuy<'info>>,
+    n_pools: u8,
+    quantity: u8,
+    max_total_price: u64,
+) -> Result<()> {
+    let n_pools = n_pools as usize;
+    let (mut pools, items) = load_route_pools(
+        ctx.program_id,
+        ctx.remaining_accounts,
+        n_pools,
+        ctx.accounts.token_mint.key(),
+    )?;
+
+    assert_is_ata(
+        &ctx.accounts.authority_token_ata.to_account_info(),
+        ctx.accounts.authority.key,
+        &ctx.accounts.token_mint.key(),
+    )?;
+
+    // (pool index, nft_vault, authority_nft_ata)
+    let mut candidates = Vec::new();
+    for chunk in ctx.remaining_accounts[items..].chunks(BUY_ITEM_ACCOUNTS) {
+        if chunk.len() != BUY_ITEM_ACCOUNTS {
+            return Err(ErrorCode::InvalidRouteAccounts.into());
+        }
+        let nft_vault = Account::<TokenAccount>::try_from(&chunk[0])?;
+        let pool_idx = pools
+            .iter()
+            .position(|p| p.pool_auth.key() == nft_vault.owner)
+            .ok_or(ErrorCode::InvalidRouteAccounts)?;
+        let (vault_key, _) = Pubkey::find_program_address(
+            &[
+                b"vault",
+                pools[pool_idx].pool.key().as_ref(),
+                nft_vault.mint.as_ref(),
+            ],
+            ctx.program_id,
+        );
+        assert_keys_equal(vault_key, nft_vault.key())?;
+        assert_is_ata(&chunk[1], ctx.accounts.authority.key, &nft_vault.mint)?;
+        candidates.push((pool_idx, nft_vault, chunk[1].clone()));
+    }
+
+    let mut used = vec![false; candidates.len()];
+    let mut total_price: u64 = 0;
+    for _ in 0..quantity {
+        // cheapest pool that still has an unused candidate
+        let mut best: Option<(usize, u64)> = None;
+        for (i, (pool_idx, nft_vault, _)) in candidates.iter().enumerate() {
+            if used[i] || nft_vault.amount == 0 || pools[*pool_idx].expired {
+                continue;
+            }
+            // pools that cannot sell another nft, e.g. at their floor, are skipped
+            let price = match pools[*pool_idx].pool.route_buy_quote() {
+                Some(price) => price,
+                None => continue,
+            };
+            let better = match best {
+                Some((_, best_price)) => price < best_price,
+                None => true,
+            };
+            if better {
+                best = Some((i, price));
+            }
+        }
+        let (i, _) = best.ok_or(ErrorCode::NotEnoughOut)?;
+        used[i] = true;
+
+        let (pool_idx, nft_vault, authority_nft_ata) = &candidates[i];
+        let route_pool = &mut pools[*pool_idx];
+        let pool_key = route_pool.pool.key();
+        let fill = route_pool.pool.buy()?;
+        let pda_sign = &[
+            b"pool_auth",
+            pool_key.as_ref(),
+            &[route_pool.pool_auth_bump],
+        ];
+
+        transfer(
+            &ctx.accounts.token_program,
+            nft_vault.to_account_info(),
+            authority_nft_ata.clone(),
+            route_pool.pool_auth.clone(),
+            &[pda_sign],
+            1,
+        )?;
+
+        transfer(
+            &ctx.accounts.token_program,
+            ctx.accounts.authority_token_ata.to_account_info(),
+            route_pool.token_vault.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            &[],
+            fill.cost()?,
+        )?;
+
+        route_pool
+            .inventory
+            .remove(&mut route_pool.pool, nft_vault.mint)?;
+        let pool = &route_pool.pool;
+
+        emit!(Swap {
+            pool: pool_key,
+            nft_mint: nft_vault.mint,
+            trader: ctx.accounts.authority.key(),
+            buy: true,
+            price: fill.price,
+            pool_fee: fill.pool_fee,
+            curator_fee: fill.curator_fee,
+            spot_price: pool.spot_price,
+            n_nft: pool.n_nft,
+            n_token: pool.n_token,
+        });
+
+        total_price = total_price
+            .checked_add(fill.cost()?)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    if total_price > max_total_price {
+        return Err(ErrorCode::SlippageExceeded.into());
+    }
+
+    for route_pool in pools.iter() {
+        ctx.accounts
+            .collection_index
+            .update_spot_price(route_pool.pool.key(), route_pool.pool.spot_price);
+        route_pool.pool.exit(ctx.program_id)?;
+        route_pool.inventory.exit(ctx.program_id)?;
+    }
+    Ok(())
+}
+
+pub fn route_sell<'info>(
+    ctx: Context<'_, '_, '_, 'info, RouteSell<'info>>,
+    n_pools: u8,
+    min_total_out: u64,
+    merkle_proofs: Vec<Vec<[u8; 32]>>,
+) -> Result<()> {
+    let n_pools = n_pools as usize;
+    let (mut pools, items) = load_route_pools(
+        ctx.program_id,
+        ctx.remaining_accounts,
+        n_pools,
+        ctx.accounts.token_mint.key(),
+    )?;
+
+    assert_is_ata(
+        &ctx.accounts.authority_token_ata.to_account_info(),
+        ctx.accounts.authority.key,
+        &ctx.accounts.token_mint.key(),
+    )?;
+
+    let collection = pools[0].pool.collection;
+    let mut total_out: u64 = 0;
+    let item_accounts = SELL_ITEM_ACCOUNTS + n_pools;
+    for (item, chunk) in ctx.remaining_accounts[items..]
+        .chunks(item_accounts)
+        .enumerate()
+    {
+        if chunk.len() != item_accounts {
+            return Err(ErrorCode::InvalidRouteAccounts.into());
+        }
+        let nft_mint = &chunk[0];
+        let nft_mint_metadata = &chunk[1];
+        let authority_nft_ata = &chunk[2];
+        let nft_vaults = &chunk[SELL_ITEM_ACCOUNTS..];
+
+        // every pool shares the collection, so it is checked once per item
+        if let Some(collection) = collection {
+            asset_collection(nft_mint, nft_mint_metadata, collection)?;
+        }
+        assert_is_ata(authority_nft_ata, ctx.accounts.authority.key, nft_mint.key)?;
+        let merkle_proof = merkle_proofs.get(item).cloned().unwrap_or_default();
+
+        // pool paying the most for this nft
+        let mut best: Option<(usize, u64)> = None;
+        for (pool_idx, route_pool) in pools.iter().enumerate() {
+            let pool = &route_pool.pool;
+            if route_pool.expired {
+                continue;
+            }
+            // pools that cannot take another nft are skipped rather than failing the route
+            let price = match pool.route_sell_quote() {
+                Some(price) => price,
+                None => continue,
+            };
+            if let Some(merkle_root) = pool.merkle_root {
+                if asset_merkle(nft_mint.key(), merkle_root, merkle_proof.clone()).is_err() {
+                    continue;
+                }
+            }
+            let better = match best {
+                Some((_, best_price)) => price > best_price,
+                None => true,
+            };
+            if better {
+                best = Some((pool_idx, price));
+            }
+        }
+        let (pool_idx, _) = best.ok_or(ErrorCode::NotEnoughOut)?;
+
+        let route_pool = &mut pools[pool_idx];
+        let pool_key = route_pool.pool.key();
+        let nft_vault = Account::<TokenAccount>::try_from(&nft_vaults[pool_idx])?;
+        let (vault_key, _) = Pubkey::find_program_address(
+            &[b"vault", pool_key.as_ref(), nft_mint.key.as_ref()],
+            ctx.program_id,
+        );
+        assert_keys_equal(vault_key, nft_vault.key())?;
+        assert_keys_equal(nft_vault.owner, route_pool.pool_auth.key())?;
+        assert_keys_equal(nft_vault.mint, nft_mint.key())?;
+        if nft_vault.amount != 0 {
+            return Err(ErrorCode::InvalidRouteAccounts.into());
+        }
+
+        let pda_sign = &[
+            b"pool_auth",
+            pool_key.as_ref(),
+            &[route_pool.pool_auth_bump],
+        ];
+        let fill = route_pool.pool.sell()?;
+
+        transfer(
+            &ctx.accounts.token_program,
+            route_pool.token_vault.to_account_info(),
+            ctx.accounts.authority_token_ata.to_account_info(),
+            route_pool.pool_auth.clone(),
+            &[pda_sign],
+            fill.price,
+        )?;
+
+        transfer(
+            &ctx.accounts.token_program,
+            authority_nft_ata.clone(),
+            nft_vault.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            &[],
+            1,
+        )?;
+
+        transfer(
+            &ctx.accounts.token_program,
+            ctx.accounts.authority_token_ata.to_account_info(),
+            route_pool.token_vault.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            &[],
+            fill.fees()?,
+        )?;
+
+        route_pool
+            .inventory
+            .insert(&route_pool.pool, nft_mint.key())?;
+        let pool = &route_pool.pool;
+
+        emit!(Swap {
+            pool: pool_key,
+            nft_mint: nft_mint.key(),
+            trader: ctx.accounts.authority.key(),
+            buy: false,
+            price: fill.price,
+            pool_fee: fill.pool_fee,
+            curator_fee: fill.curator_fee,
+            spot_price: pool.spot_price,
+            n_nft: pool.n_nft,
+            n_token: pool.n_token,
+        });
+
+        total_out = total_out
+            .checked_add(fill.proceeds()?)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    if total_out < min_total_out {
+        return Err(ErrorCode::SlippageExceeded.into());
+    }
+
+    for route_pool in pools.iter() {
+        ctx.accounts
+            .collection_index
+            .update_spot_price(route_pool.pool.key(), route_pool.pool.spot_price);
+        route_pool.pool.exit(ctx.program_id)?;
+        route_pool.inventory.exit(ctx.program_id)?;
+    }
+    Ok(())
+}
+
+fn load_route_pools<'info>(
+    program_id: &Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+    n_pools: usize,
+    mint: Pubkey,
+) -> Result<(Vec<RoutePool<'info>>, usize)> {
+    if n_pools == 0 || remaining_accounts.len() < n_pools * POOL_ACCOUNTS {
+        return Err(ErrorCode::InvalidRouteAccounts.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut price_feeds = remaining_accounts[n_pools * POOL_ACCOUNTS..].iter();
+    let mut pools: Vec<RoutePool<'info>> = Vec::with_capacity(n_pools);
+    for chunk in remaining_accounts[..n_pools * POOL_ACCOUNTS].chunks(POOL_ACCOUNTS) {
+        let mut pool = Account::<Pool>::try_from(&chunk[0])?;
+        assert_keys_equal(pool.mint, mint)?;
+        pool.sync_price_feed(&mut price_feeds)?;
+        pool.apply_decay(now);
+        // the same pool twice would let one curve step be filled at a stale price
+        if pools.iter().any(|p| p.pool.key() == pool.key()) {
+            return Err(ErrorCode::InvalidRouteAccounts.into());
+        }
+        if let Some(first) = pools.first() {
+            if first.pool.collection != pool.collection {
+                return Err(ErrorCode::CollectionMismatch.into());
+            }
+        }
+
+        let (pool_auth, pool_auth_bump) =
+            Pubkey::find_program_address(&[b"pool_auth", pool.key().as_ref()], program_id);
+        assert_keys_equal(pool_auth, chunk[1].key())?;
+        let (token_vault, _) =
+            Pubkey::find_program_address(&[b"token_vault", pool.key().as_ref()], program_id);
+        assert_keys_equal(token_vault, chunk[2].key())?;
+
+        let inventory = Account::<Inventory>::try_from(&chunk[3])?;
+        assert_keys_equal(inventory.pool, pool.key())?;
+
+        let expired = pool.is_expired(now) || pool.check_oracle(now).is_err();
+        pools.push(RoutePool {
+            pool,
+            pool_auth: chunk[1].clone(),
+            pool_auth_bump,
+            token_vault: Account::<TokenAccount>::try_from(&chunk[2])?,
+            inventory,
+            expired,
+        });
+    }
+    // items start after the last price feed
+    let items = remaining_accounts.len() - price_feeds.len();
+    Ok((pools, items))
+}
+
+fn transfer<'info>(
+    token_program: &Program<'info, Token>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    token::transfer(
+        CpiContext::new(
+            token_program.to_account_info(),
+            Transfer {
+                from,
+                to,
+                authority,
+            },
+        )
+        .with_signer(signer_seeds),
+        amount,
+    )
+}
+
+#[derive(Accounts)]
+pub struct RouteBuy<'info> {
+    // pools may list in different indexes, only those listed here are refreshed
+    #[account(mut)]
+    pub collection_index: Box<Account<'info, CollectionIndex>>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority_token_ata: Box<Account<'info, TokenAccount>>,
+
+    pub authority: Signer<'info>,
+
+    // other
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RouteSell<'info> {
+    // pools may list in different indexes, only those listed here are refreshed
+    #[account(mut)]
+    pub collection_index: Box<Account<'info, CollectionIndex>>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority_token_ata: Box<Account<'info, TokenAccount>>,
+
+    pub authority: Signer<'info>,
+
+    // other
+    pub token_program: Program<'info, Token>,
+}
@@ -6,30 +6,66 @@ use anchor_spl::{
 
 use crate::{
     error::ErrorCode,
-    utils::{assert_is_ata, asset_merkle},
+    events::{Referral, Swap},
+    utils::{assert_is_ata, assert_keys_equal, assert_taker},
+};
+use crate::{
+    state::{BuyerRecord, CollectionIndex, Inventory, Pool},
+    utils::asset_collection,
 };
-use crate::{state::Pool, utils::asset_collection};
 
-pub fn swap_for_nft(ctx: Context<SwapForNFT>) -> Result<()> {
+// remaining accounts: the pool's PriceFeed for ORACLE pools, the buyer's BuyerRecord when the
+// pool has a buy_limit, then the referrer's token account when referral_bps is nonzero
+pub fn swap_for_nft<'info>(
+    ctx: Context<'_, '_, '_, 'info, SwapForNFT<'info>>,
+    merkle_proof: Vec<[u8; 32]>,
+    multiplier_bps: u64,
+    taker_proof: Vec<[u8; 32]>,
+    referral_bps: u64,
+) -> Result<()> {
     let pool_key = ctx.accounts.pool.key();
     let pool = &mut ctx.accounts.pool;
-    if pool.pool_type == 0 {
-        return Err(ErrorCode::InvalidPoolType.into());
+    let now = Clock::get()?.unix_timestamp;
+    pool.check_expiry(now)?;
+    let mut remaining_accounts = ctx.remaining_accounts.iter();
+    pool.sync_price_feed(&mut remaining_accounts)?;
+    pool.check_oracle(now)?;
+    if let Some(taker_root) = pool.taker_root {
+        assert_taker(ctx.accounts.authority.key(), taker_root, taker_proof)?;
     }
+    pool.apply_decay(now);
 
     assert_is_ata(
         &ctx.accounts.authority_token_ata.to_account_info(),
-        &ctx.accounts.authority.key,
+        ctx.accounts.authority.key,
         &pool.mint,
     )?;
 
     assert_is_ata(
         &ctx.accounts.authority_nft_ata.to_account_info(),
-        &ctx.accounts.authority.key,
+        ctx.accounts.authority.key,
         &ctx.accounts.nft_mint.key(),
     )?;
+    let multiplier_bps =
+        pool.check_inventory_proof(ctx.accounts.nft_mint.key(), multiplier_bps, merkle_proof)?;
+
+    let fill = pool.buy_at(multiplier_bps)?;
+    let referral_fee = pool.referral_fee(fill.price, referral_bps)?;
+
+    if pool.buy_limit != 0 {
+        let info = remaining_accounts
+            .next()
+            .ok_or(ErrorCode::MissingBuyerRecord)?;
+        record_buyer(
+            ctx.program_id,
+            pool_key,
+            ctx.accounts.authority.key(),
+            pool.buy_limit,
+            info,
+        )?;
+    }
 
-    let bump = *ctx.bumps.get("pool_auth").unwrap();
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
     let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
 
     token::transfer(
@@ -37,7 +73,7 @@ pub fn swap_for_nft(ctx: Context<SwapForNFT>) -> Result<()> {
             ctx.accounts.token_program.to_account_info(),
             Transfer {
                 from: ctx.accounts.nft_vault.to_account_info(),
-                to: ctx.accounts.authority_token_ata.to_account_info(),
+                to: ctx.accounts.authority_nft_ata.to_account_info(),
                 authority: ctx.accounts.pool_auth.to_account_info(),
             },
         )
@@ -54,36 +90,134 @@ pub fn swap_for_nft(ctx: Context<SwapForNFT>) -> Result<()> {
                 authority: ctx.accounts.authority.to_account_info(),
             },
         ),
-        pool.spot_price,
+        fill.cost()?,
     )?;
 
-    if pool.pool_type == 2 {
-        let pool_fee = pool
-            .spot_price
-            .checked_mul(pool.fee_bps)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap();
+    if referral_bps != 0 {
+        let referrer = remaining_accounts
+            .next()
+            .ok_or(ErrorCode::MissingReferrer)?;
+        pay_referral(
+            pool_key,
+            pool.mint,
+            referral_fee,
+            referrer,
+            &ctx.accounts.authority_token_ata,
+            &ctx.accounts.authority,
+            &ctx.accounts.token_program,
+        )?;
+    }
 
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.authority_token_ata.to_account_info(),
-                    to: ctx.accounts.token_vault.to_account_info(),
-                    authority: ctx.accounts.authority.to_account_info(),
-                },
-            ),
-            pool_fee,
+    ctx.accounts
+        .inventory
+        .remove(pool, ctx.accounts.nft_mint.key())?;
+    ctx.accounts
+        .collection_index
+        .update_spot_price(pool_key, pool.spot_price);
+
+    emit!(Swap {
+        pool: pool_key,
+        nft_mint: ctx.accounts.nft_mint.key(),
+        trader: ctx.accounts.authority.key(),
+        buy: true,
+        price: fill.price,
+        pool_fee: fill.pool_fee,
+        curator_fee: fill.curator_fee,
+        spot_price: pool.spot_price,
+        n_nft: pool.n_nft,
+        n_token: pool.n_token,
+    });
+    Ok(())
+}
+
+// swap_for_nft without naming the nft: the buyer always gets the oldest nft in the inventory,
+// skipping nfts a rechecked root no longer covers.
+// remaining accounts: PriceFeed, BuyerRecord and referrer as in swap_for_nft, then (nft_vault, authority_nft_ata)
+// candidate pairs, several can be passed so the fill survives other buyers landing first
+pub fn buy_any<'info>(
+    ctx: Context<'_, '_, '_, 'info, BuyAny<'info>>,
+    merkle_proof: Vec<[u8; 32]>,
+    multiplier_bps: u64,
+    taker_proof: Vec<[u8; 32]>,
+    referral_bps: u64,
+) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let pool = &mut ctx.accounts.pool;
+    let now = Clock::get()?.unix_timestamp;
+    pool.check_expiry(now)?;
+    let mut remaining_accounts = ctx.remaining_accounts.iter();
+    pool.sync_price_feed(&mut remaining_accounts)?;
+    pool.check_oracle(now)?;
+    if let Some(taker_root) = pool.taker_root {
+        assert_taker(ctx.accounts.authority.key(), taker_root, taker_proof)?;
+    }
+    pool.apply_decay(now);
+
+    assert_is_ata(
+        &ctx.accounts.authority_token_ata.to_account_info(),
+        ctx.accounts.authority.key,
+        &pool.mint,
+    )?;
+
+    // fifo, so which nft is delivered does not depend on who lands first
+    let (nft_mint, multiplier_bps) =
+        ctx.accounts
+            .inventory
+            .oldest_provable(pool, multiplier_bps, &merkle_proof)?;
+
+    let fill = pool.buy_at(multiplier_bps)?;
+    let referral_fee = pool.referral_fee(fill.price, referral_bps)?;
+
+    if pool.buy_limit != 0 {
+        let info = remaining_accounts
+            .next()
+            .ok_or(ErrorCode::MissingBuyerRecord)?;
+        record_buyer(
+            ctx.program_id,
+            pool_key,
+            ctx.accounts.authority.key(),
+            pool.buy_limit,
+            info,
         )?;
     }
+    let referrer = if referral_bps != 0 {
+        Some(
+            remaining_accounts
+                .next()
+                .ok_or(ErrorCode::MissingReferrer)?,
+        )
+    } else {
+        None
+    };
+
+    let candidates: Vec<&AccountInfo<'info>> = remaining_accounts.collect();
+    let (vault_key, _) = Pubkey::find_program_address(
+        &[b"vault", pool_key.as_ref(), nft_mint.as_ref()],
+        ctx.program_id,
+    );
+    let pair = candidates
+        .chunks_exact(2)
+        .find(|pair| pair[0].key() == vault_key)
+        .ok_or(ErrorCode::NoCandidate)?;
+    let nft_vault = pair[0];
+    let authority_nft_ata = pair[1];
+    assert_is_ata(authority_nft_ata, ctx.accounts.authority.key, &nft_mint)?;
+
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
+    let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
 
-    let curator_fee = pool
-        .spot_price
-        .checked_mul(pool.curator_fee_bps)
-        .unwrap()
-        .checked_div(10000)
-        .unwrap();
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: nft_vault.clone(),
+                to: authority_nft_ata.clone(),
+                authority: ctx.accounts.pool_auth.to_account_info(),
+            },
+        )
+        .with_signer(&[pda_sign]),
+        1,
+    )?;
 
     token::transfer(
         CpiContext::new(
@@ -94,68 +228,89 @@ pub fn swap_for_nft(ctx: Context<SwapForNFT>) -> Result<()> {
                 authority: ctx.accounts.authority.to_account_info(),
             },
         ),
-        curator_fee,
+        fill.cost()?,
     )?;
 
-    let new_spot_price;
-    if pool.curve_type == 0 {
-        // linear curve
-        new_spot_price = (pool.spot_price as i64)
-            .checked_add(pool.delta as i64)
-            .unwrap();
-    } else {
-        // exponential curve
-        new_spot_price = (pool.spot_price as i64)
-            .checked_mul((100 as i64).checked_add(pool.delta).unwrap())
-            .unwrap()
-            .checked_div(100 as i64)
-            .unwrap();
-    };
-    if new_spot_price < 0 {
-        return Err(ErrorCode::InvalidSpotPrice.into());
-    };
-    pool.spot_price = new_spot_price as u64;
-    pool.n_token = ctx.accounts.token_vault.amount;
-    pool.n_nft -= 1;
+    if let Some(referrer) = referrer {
+        pay_referral(
+            pool_key,
+            pool.mint,
+            referral_fee,
+            referrer,
+            &ctx.accounts.authority_token_ata,
+            &ctx.accounts.authority,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    ctx.accounts.inventory.remove(pool, nft_mint)?;
+    ctx.accounts
+        .collection_index
+        .update_spot_price(pool_key, pool.spot_price);
+
+    emit!(Swap {
+        pool: pool_key,
+        nft_mint,
+        trader: ctx.accounts.authority.key(),
+        buy: true,
+        price: fill.price,
+        pool_fee: fill.pool_fee,
+        curator_fee: fill.curator_fee,
+        spot_price: pool.spot_price,
+        n_nft: pool.n_nft,
+        n_token: pool.n_token,
+    });
     Ok(())
 }
 
-pub fn swap_for_token(ctx: Context<SwapForToken>, merkle_proof: Vec<[u8; 32]>) -> Result<()> {
+// remaining accounts: the pool's PriceFeed for ORACLE pools, then the referrer's token account
+// when referral_bps is nonzero
+pub fn swap_for_token<'info>(
+    ctx: Context<'_, '_, '_, 'info, SwapForToken<'info>>,
+    merkle_proof: Vec<[u8; 32]>,
+    multiplier_bps: u64,
+    taker_proof: Vec<[u8; 32]>,
+    referral_bps: u64,
+) -> Result<()> {
     let pool_key = ctx.accounts.pool.key();
     let pool = &mut ctx.accounts.pool;
-    if pool.pool_type == 0 {
-        return Err(ErrorCode::InvalidPoolType.into());
+    let now = Clock::get()?.unix_timestamp;
+    pool.check_expiry(now)?;
+    let mut remaining_accounts = ctx.remaining_accounts.iter();
+    pool.sync_price_feed(&mut remaining_accounts)?;
+    pool.check_oracle(now)?;
+    if let Some(taker_root) = pool.taker_root {
+        assert_taker(ctx.accounts.authority.key(), taker_root, taker_proof)?;
     }
+    pool.apply_decay(now);
 
-    if pool.collection.is_some() {
+    if let Some(collection) = pool.collection {
         asset_collection(
             &ctx.accounts.nft_mint.to_account_info(),
             &ctx.accounts.nft_mint_metadata,
-            pool.collection.unwrap(),
+            collection,
         )?;
     }
 
-    if pool.merkle_root.is_some() {
-        asset_merkle(
-            ctx.accounts.nft_mint.key(),
-            pool.merkle_root.unwrap(),
-            merkle_proof,
-        )?;
-    }
+    let multiplier_bps =
+        pool.check_nft_proof(ctx.accounts.nft_mint.key(), multiplier_bps, merkle_proof)?;
 
     assert_is_ata(
         &ctx.accounts.authority_token_ata.to_account_info(),
-        &ctx.accounts.authority.key,
+        ctx.accounts.authority.key,
         &pool.mint,
     )?;
 
     assert_is_ata(
         &ctx.accounts.authority_nft_ata.to_account_info(),
-        &ctx.accounts.authority.key,
+        ctx.accounts.authority.key,
         &ctx.accounts.nft_mint.key(),
     )?;
 
-    let bump = *ctx.bumps.get("pool_auth").unwrap();
+    let fill = pool.sell_at(multiplier_bps)?;
+    let referral_fee = pool.referral_fee(fill.price, referral_bps)?;
+
+    let bump = *ctx.bumps.get("pool_auth").ok_or(ErrorCode::MissingBump)?;
     let pda_sign = &[b"pool_auth", pool_key.as_ref(), &[bump]];
 
     token::transfer(
@@ -168,10 +323,10 @@ pub fn swap_for_token(ctx: Context<SwapForToken>, merkle_proof: Vec<[u8; 32]>) -
             },
         )
         .with_signer(&[pda_sign]),
-        pool.spot_price,
+        fill.price,
     )?;
 
-    assert!(ctx.accounts.nft_vault.amount == 0);
+    require!(ctx.accounts.nft_vault.amount == 0, ErrorCode::VaultNotEmpty);
     token::transfer(
         CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
@@ -184,34 +339,6 @@ pub fn swap_for_token(ctx: Context<SwapForToken>, merkle_proof: Vec<[u8; 32]>) -
         1,
     )?;
 
-    if pool.pool_type == 2 {
-        let pool_fee = pool
-            .spot_price
-            .checked_mul(pool.fee_bps)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap();
-
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.authority_token_ata.to_account_info(),
-                    to: ctx.accounts.token_vault.to_account_info(),
-                    authority: ctx.accounts.authority.to_account_info(),
-                },
-            ),
-            pool_fee,
-        )?;
-    }
-
-    let curator_fee = pool
-        .spot_price
-        .checked_mul(pool.curator_fee_bps)
-        .unwrap()
-        .checked_div(10000)
-        .unwrap();
-
     token::transfer(
         CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
@@ -221,29 +348,93 @@ pub fn swap_for_token(ctx: Context<SwapForToken>, merkle_proof: Vec<[u8; 32]>) -
                 authority: ctx.accounts.authority.to_account_info(),
             },
         ),
-        curator_fee,
+        fill.fees()?,
     )?;
 
-    let new_spot_price;
-    if pool.curve_type == 0 {
-        // linear curve
-        new_spot_price = (pool.spot_price as i64)
-            .checked_sub(pool.delta as i64)
-            .unwrap();
-    } else {
-        // exponential curve
-        new_spot_price = (pool.spot_price as i64)
-            .checked_mul((100 as i64).checked_sub(pool.delta).unwrap())
-            .unwrap()
-            .checked_div(100 as i64)
-            .unwrap();
-    };
-    if new_spot_price < 0 {
-        return Err(ErrorCode::InvalidSpotPrice.into());
-    };
-    pool.spot_price = new_spot_price as u64;
-    pool.n_token = ctx.accounts.token_vault.amount;
-    pool.n_nft += 1;
+    if referral_bps != 0 {
+        let referrer = remaining_accounts
+            .next()
+            .ok_or(ErrorCode::MissingReferrer)?;
+        pay_referral(
+            pool_key,
+            pool.mint,
+            referral_fee,
+            referrer,
+            &ctx.accounts.authority_token_ata,
+            &ctx.accounts.authority,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    ctx.accounts
+        .inventory
+        .insert(pool, ctx.accounts.nft_mint.key())?;
+    ctx.accounts
+        .collection_index
+        .update_spot_price(pool_key, pool.spot_price);
+
+    emit!(Swap {
+        pool: pool_key,
+        nft_mint: ctx.accounts.nft_mint.key(),
+        trader: ctx.accounts.authority.key(),
+        buy: false,
+        price: fill.price,
+        pool_fee: fill.pool_fee,
+        curator_fee: fill.curator_fee,
+        spot_price: pool.spot_price,
+        n_nft: pool.n_nft,
+        n_token: pool.n_token,
+    });
+    Ok(())
+}
+
+fn record_buyer(
+    program_id: &Pubkey,
+    pool_key: Pubkey,
+    buyer: Pubkey,
+    buy_limit: u64,
+    info: &AccountInfo,
+) -> Result<()> {
+    let mut record = Account::<BuyerRecord>::try_from(info)?;
+    if record.pool != pool_key || record.buyer != buyer {
+        return Err(ErrorCode::PublicKeyMismatch.into());
+    }
+    record.record_buy(buy_limit)?;
+    record.exit(program_id)
+}
+
+// the taker pays the referral from authority_token_ata, it never passes through the pool
+fn pay_referral<'info>(
+    pool_key: Pubkey,
+    mint: Pubkey,
+    referral_fee: u64,
+    referrer: &AccountInfo<'info>,
+    authority_token_ata: &Account<'info, TokenAccount>,
+    authority: &Signer<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let referrer_ata = Account::<TokenAccount>::try_from(referrer)?;
+    assert_keys_equal(referrer_ata.mint, mint)?;
+
+    if referral_fee > 0 {
+        token::transfer(
+            CpiContext::new(
+                token_program.to_account_info(),
+                Transfer {
+                    from: authority_token_ata.to_account_info(),
+                    to: referrer.clone(),
+                    authority: authority.to_account_info(),
+                },
+            ),
+            referral_fee,
+        )?;
+    }
+
+    emit!(Referral {
+        pool: pool_key,
+        referrer: referrer.key(),
+        amount: referral_fee,
+    });
     Ok(())
 }
 
@@ -252,15 +443,21 @@ pub struct SwapForNFT<'info> {
     #[account(mut, constraint=pool.valid)]
     pub pool: Box<Account<'info, Pool>>,
 
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(mut, constraint = pool.is_indexed_in(&collection_index))]
+    pub collection_index: Box<Account<'info, CollectionIndex>>,
+
     #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
     pub pool_auth: AccountInfo<'info>,
 
-    #[account(seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
+    #[account(mut, seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
     pub nft_vault: Box<Account<'info, TokenAccount>>,
 
     pub nft_mint: Account<'info, Mint>,
 
-    #[account(seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
     pub token_vault: Box<Account<'info, TokenAccount>>,
 
     pub token_mint: Account<'info, Mint>,
@@ -277,22 +474,56 @@ pub struct SwapForNFT<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct BuyAny<'info> {
+    #[account(mut, constraint=pool.valid)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(mut, constraint = pool.is_indexed_in(&collection_index))]
+    pub collection_index: Box<Account<'info, CollectionIndex>>,
+
+    #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
+    pub pool_auth: AccountInfo<'info>,
+
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority_token_ata: Box<Account<'info, TokenAccount>>,
+
+    pub authority: Signer<'info>,
+
+    // other
+    pub token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct SwapForToken<'info> {
     #[account(mut, constraint=pool.valid)]
     pub pool: Box<Account<'info, Pool>>,
 
+    #[account(mut, has_one = pool)]
+    pub inventory: Box<Account<'info, Inventory>>,
+
+    #[account(mut, constraint = pool.is_indexed_in(&collection_index))]
+    pub collection_index: Box<Account<'info, CollectionIndex>>,
+
     #[account(seeds=[b"pool_auth", pool.key().as_ref()], bump)]
     pub pool_auth: AccountInfo<'info>,
 
-    #[account(seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
+    #[account(mut, seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
     pub nft_vault: Box<Account<'info, TokenAccount>>,
 
     pub nft_mint: Account<'info, Mint>,
 
     pub nft_mint_metadata: AccountInfo<'info>,
 
-    #[account(seeds=[b"vault", pool.key().as_ref(), nft_mint.key().as_ref()], bump)]
+    #[account(mut, seeds=[b"token_vault", pool.key().as_ref()], bump)]
     pub token_vault: Box<Account<'info, TokenAccount>>,
 
     pub token_mint: Account<'info, Mint>,
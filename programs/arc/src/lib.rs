@@ -1,8 +1,11 @@
 use anchor_lang::prelude::*;
 
 pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod machine;
 pub mod state;
+pub mod units;
 pub mod utils;
 
 use instructions::*;
@@ -16,45 +19,231 @@ pub mod arc {
     pub fn initialize_pool(
         ctx: Context<InitializePool>,
         owner_nonce: u8,
-        curve_type: u8,
-        delta: i64,
-        spot_price: u64,
         pool_type: u8,
         fee_bps: u64,
         curator_fee_bps: u64,
         merkle_root: [u8; 32],
+        curve: CurveArgs,
     ) -> Result<()> {
         init_pool::handler(
             ctx,
             owner_nonce,
-            curve_type,
-            delta,
-            spot_price,
             pool_type,
             fee_bps,
             curator_fee_bps,
             merkle_root,
+            curve,
         )
     }
 
-    pub fn add_nft(ctx: Context<AddNFTLiquidity>, merkle_proof: Vec<[u8; 32]>) -> Result<()> {
-        liquidity::add_nft(ctx, merkle_proof)
+    pub fn close_pool<'info>(ctx: Context<'_, '_, '_, 'info, ClosePool<'info>>) -> Result<()> {
+        close_pool::close_pool(ctx)
+    }
+
+    pub fn init_collection_index(
+        ctx: Context<InitCollectionIndex>,
+        key: [u8; 32],
+        page: u32,
+    ) -> Result<()> {
+        index::init_collection_index(ctx, key, page)
+    }
+
+    pub fn init_inventory_page(ctx: Context<InitInventoryPage>) -> Result<()> {
+        inventory::init_inventory_page(ctx)
+    }
+
+    pub fn advance_inventory_head(ctx: Context<AdvanceInventoryHead>) -> Result<()> {
+        inventory::advance_inventory_head(ctx)
+    }
+
+    pub fn add_nft(
+        ctx: Context<AddNFTLiquidity>,
+        merkle_proof: Vec<[u8; 32]>,
+        multiplier_bps: u64,
+    ) -> Result<()> {
+        liquidity::add_nft(ctx, merkle_proof, multiplier_bps)
     }
     pub fn remove_nft(ctx: Context<RemoveNFTLiquidity>) -> Result<()> {
         liquidity::remove_nft(ctx)
     }
+    pub fn add_nfts<'info>(
+        ctx: Context<'_, '_, '_, 'info, AddNFTs<'info>>,
+        merkle_proofs: Vec<Vec<[u8; 32]>>,
+        multipliers_bps: Vec<u64>,
+    ) -> Result<()> {
+        liquidity::add_nfts(ctx, merkle_proofs, multipliers_bps)
+    }
+    pub fn remove_nfts<'info>(ctx: Context<'_, '_, '_, 'info, RemoveNFTs<'info>>) -> Result<()> {
+        liquidity::remove_nfts(ctx)
+    }
     pub fn add_token_liquidity(ctx: Context<AddTokenLiquidity>, amount: u64) -> Result<()> {
         liquidity::add_token_liquidity(ctx, amount)
     }
     pub fn remove_token_liquidity(ctx: Context<RemoveTokenLiquidity>, amount: u64) -> Result<()> {
         liquidity::remove_token_liquidity(ctx, amount)
     }
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>) -> Result<()> {
+        liquidity::withdraw_fees(ctx)
+    }
+
+    pub fn edit_delta(ctx: Context<EditDelta>, delta: i64) -> Result<()> {
+        edit::edit_delta(ctx, delta)
+    }
+    pub fn edit_spot_price(ctx: Context<EditSpotPrice>, spot_price: u64) -> Result<()> {
+        edit::edit_spot_price(ctx, spot_price)
+    }
+    pub fn set_price_bounds(
+        ctx: Context<SetPriceBounds>,
+        min_price: u64,
+        max_price: u64,
+    ) -> Result<()> {
+        edit::set_price_bounds(ctx, min_price, max_price)
+    }
+    pub fn set_spread(ctx: Context<SetSpread>, spread_bps: u64) -> Result<()> {
+        edit::set_spread(ctx, spread_bps)
+    }
+    pub fn set_expiry(ctx: Context<SetExpiry>, expires_at: i64) -> Result<()> {
+        edit::set_expiry(ctx, expires_at)
+    }
+    pub fn set_taker_root(ctx: Context<SetTakerRoot>, taker_root: Option<[u8; 32]>) -> Result<()> {
+        edit::set_taker_root(ctx, taker_root)
+    }
+    pub fn set_buy_limit(ctx: Context<SetBuyLimit>, buy_limit: u64) -> Result<()> {
+        edit::set_buy_limit(ctx, buy_limit)
+    }
+    pub fn set_max_referral_bps(
+        ctx: Context<SetMaxReferralBps>,
+        max_referral_bps: u64,
+    ) -> Result<()> {
+        edit::set_max_referral_bps(ctx, max_referral_bps)
+    }
+    pub fn set_flash_fee(ctx: Context<SetFlashFee>, flash_fee: Option<u64>) -> Result<()> {
+        edit::set_flash_fee(ctx, flash_fee)
+    }
+    pub fn set_rental_rate(ctx: Context<SetRentalRate>, rental_rate: Option<u64>) -> Result<()> {
+        edit::set_rental_rate(ctx, rental_rate)
+    }
+    pub fn set_merkle_root(
+        ctx: Context<SetMerkleRoot>,
+        merkle_root: Option<[u8; 32]>,
+        recheck_inventory: bool,
+        priced_leaves: bool,
+    ) -> Result<()> {
+        edit::set_merkle_root(ctx, merkle_root, recheck_inventory, priced_leaves)
+    }
+    pub fn set_oracle(ctx: Context<SetOracle>, oracle_bps: u64, max_staleness: i64) -> Result<()> {
+        edit::set_oracle(ctx, oracle_bps, max_staleness)
+    }
+
+    pub fn init_price_feed(ctx: Context<InitPriceFeed>, price: u64) -> Result<()> {
+        oracle::init_price_feed(ctx, price)
+    }
+    pub fn update_price_feed(ctx: Context<UpdatePriceFeed>, price: u64) -> Result<()> {
+        oracle::update_price_feed(ctx, price)
+    }
+    pub fn sync_oracle(ctx: Context<SyncOracle>) -> Result<()> {
+        oracle::sync_oracle(ctx)
+    }
+
+    pub fn quote(ctx: Context<QuotePool>, multiplier_bps: u64) -> Result<()> {
+        quote::quote(ctx, multiplier_bps)
+    }
+
+    pub fn init_lp_mint(ctx: Context<InitLpMint>) -> Result<()> {
+        lp::init_lp_mint(ctx)
+    }
+    pub fn add_shared_liquidity(
+        ctx: Context<AddSharedLiquidity>,
+        max_token_amount: u64,
+        merkle_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        lp::add_shared_liquidity(ctx, max_token_amount, merkle_proof)
+    }
+    pub fn remove_shared_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, RemoveSharedLiquidity<'info>>,
+        lp_amount: u64,
+    ) -> Result<()> {
+        lp::remove_shared_liquidity(ctx, lp_amount)
+    }
+
+    pub fn swap_for_nft<'info>(
+        ctx: Context<'_, '_, '_, 'info, SwapForNFT<'info>>,
+        merkle_proof: Vec<[u8; 32]>,
+        multiplier_bps: u64,
+        taker_proof: Vec<[u8; 32]>,
+        referral_bps: u64,
+    ) -> Result<()> {
+        swap::swap_for_nft(ctx, merkle_proof, multiplier_bps, taker_proof, referral_bps)
+    }
+    pub fn buy_any<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyAny<'info>>,
+        merkle_proof: Vec<[u8; 32]>,
+        multiplier_bps: u64,
+        taker_proof: Vec<[u8; 32]>,
+        referral_bps: u64,
+    ) -> Result<()> {
+        swap::buy_any(ctx, merkle_proof, multiplier_bps, taker_proof, referral_bps)
+    }
+    pub fn swap_for_token<'info>(
+        ctx: Context<'_, '_, '_, 'info, SwapForToken<'info>>,
+        merkle_proof: Vec<[u8; 32]>,
+        multiplier_bps: u64,
+        taker_proof: Vec<[u8; 32]>,
+        referral_bps: u64,
+    ) -> Result<()> {
+        swap::swap_for_token(ctx, merkle_proof, multiplier_bps, taker_proof, referral_bps)
+    }
+
+    pub fn route_buy<'info>(
+        ctx: Context<'_, '_, '_, 'info, RouteBuy<'info>>,
+        n_pools: u8,
+        quantity: u8,
+        max_total_price: u64,
+    ) -> Result<()> {
+        route::route_buy(ctx, n_pools, quantity, max_total_price)
+    }
+    pub fn route_sell<'info>(
+        ctx: Context<'_, '_, '_, 'info, RouteSell<'info>>,
+        n_pools: u8,
+        min_total_out: u64,
+        merkle_proofs: Vec<Vec<[u8; 32]>>,
+    ) -> Result<()> {
+        route::route_sell(ctx, n_pools, min_total_out, merkle_proofs)
+    }
+
+    pub fn place_bid(
+        ctx: Context<PlaceBid>,
+        bid_nonce: u8,
+        price: u64,
+        quantity: u64,
+        expiry: i64,
+        merkle_root: [u8; 32],
+    ) -> Result<()> {
+        bid::place_bid(ctx, bid_nonce, price, quantity, expiry, merkle_root)
+    }
+    pub fn fill_bid(ctx: Context<FillBid>, merkle_proof: Vec<[u8; 32]>) -> Result<()> {
+        bid::fill_bid(ctx, merkle_proof)
+    }
+    pub fn cancel_bid(ctx: Context<CancelBid>) -> Result<()> {
+        bid::cancel_bid(ctx)
+    }
+
+    pub fn init_buyer_record(ctx: Context<InitBuyerRecord>) -> Result<()> {
+        buyer::init_buyer_record(ctx)
+    }
+
+    pub fn flash_borrow(ctx: Context<FlashBorrow>) -> Result<()> {
+        flash::flash_borrow(ctx)
+    }
+    pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+        flash::flash_repay(ctx)
+    }
 
-    pub fn swap_for_nft(ctx: Context<SwapForNFT>) -> Result<()> {
-        swap::swap_for_nft(ctx)
+    pub fn rent_nft(ctx: Context<RentNFT>, days: u64) -> Result<()> {
+        rental::rent_nft(ctx, days)
     }
-    pub fn swap_for_token(ctx: Context<SwapForToken>, merkle_proof: Vec<[u8; 32]>) -> Result<()> {
-        swap::swap_for_token(ctx, merkle_proof)
+    pub fn return_rental(ctx: Context<ReturnRental>) -> Result<()> {
+        rental::return_rental(ctx)
     }
 }
 
@@ -0,0 +1,302 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    state::{Pool, BASE_MULTIPLIER_BPS},
+};
+
+// pool bookkeeping as plain transitions on Pool. instructions run a transition,
+// then move the tokens and nfts it describes, so none of this needs an AccountInfo.
+// a transition that errors leaves the pool untouched.
+
+// token amounts of one trade against a pool
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fill {
+    pub price: u64,
+    pub pool_fee: u64,
+    pub curator_fee: u64,
+}
+
+impl Fill {
+    // paid by the buyer into token_vault
+    pub fn cost(&self) -> Result<u64> {
+        Ok(self
+            .price
+            .checked_add(self.fees()?)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    // fees the seller pays back into token_vault
+    pub fn fees(&self) -> Result<u64> {
+        Ok(self
+            .pool_fee
+            .checked_add(self.curator_fee)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    // net received by the seller
+    pub fn proceeds(&self) -> Result<u64> {
+        Ok(self.price.saturating_sub(self.fees()?))
+    }
+}
+
+impl Pool {
+    // taker buys one nft, token_vault receives fill.cost()
+    pub fn buy(&mut self) -> Result<Fill> {
+        self.buy_at(BASE_MULTIPLIER_BPS)
+    }
+
+    // taker sells one nft, token_vault pays fill.price and receives fill.fees()
+    pub fn sell(&mut self) -> Result<Fill> {
+        self.sell_at(BASE_MULTIPLIER_BPS)
+    }
+
+    // buy of an nft whose leaf prices it at multiplier_bps of the curve,
+    // the curve itself and its bounds move as for any other buy
+    pub fn buy_at(&mut self, multiplier_bps: u64) -> Result<Fill> {
+        if self.pool_type == 0 {
+            return Err(ErrorCode::InvalidPoolType.into());
+        }
+        self.check_no_flash_loan()?;
+        let n_nft = self
+            .n_nft
+            .checked_sub(1)
+            .ok_or(ErrorCode::InventoryUnderflow)?;
+        let curve_price = self.buy_price()?;
+        let price = apply_multiplier(curve_price, multiplier_bps)?;
+        let fill = Fill {
+            price,
+            pool_fee: self.pool_fee(price)?,
+            curator_fee: self.curator_fee(price)?,
+        };
+        let spot_price = self.spot_price_after_buy()?;
+        self.check_price_bounds(curve_price)?;
+        self.check_price_bounds(spot_price)?;
+        // the curator fee lands in token_vault alongside the price
+        let n_token = self
+            .n_token
+            .checked_add(price)
+            .and_then(|n| n.checked_add(fill.curator_fee))
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        self.record_pool_fee(fill.pool_fee)?;
+        self.spot_price = spot_price;
+        self.n_token = n_token;
+        self.n_nft = n_nft;
+        Ok(fill)
+    }
+
+    pub fn sell_at(&mut self, multiplier_bps: u64) -> Result<Fill> {
+        if self.pool_type == 1 {
+            return Err(ErrorCode::InvalidPoolType.into());
+        }
+        self.check_no_flash_loan()?;
+        let curve_price = self.sell_price()?;
+        let price = apply_multiplier(curve_price, multiplier_bps)?;
+        // accrued fees are never spent on a trade
+        if price > self.n_token {
+            return Err(ErrorCode::InsufficientLiquidity.into());
+        }
+        let fill = Fill {
+            price,
+            pool_fee: self.pool_fee(price)?,
+            curator_fee: self.curator_fee(price)?,
+        };
+        let spot_price = self.spot_price_after_sell()?;
+        self.check_price_bounds(curve_price)?;
+        self.check_price_bounds(spot_price)?;
+        let n_token = (self.n_token - price)
+            .checked_add(fill.curator_fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let n_nft = self.n_nft.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+
+        self.record_pool_fee(fill.pool_fee)?;
+        self.spot_price = spot_price;
+        self.n_token = n_token;
+        self.n_nft = n_nft;
+        Ok(fill)
+    }
+
+    // both sides of the next trade without applying them, None where the pool would refuse
+    pub fn quote(&self) -> (Option<Fill>, Option<Fill>) {
+        self.quote_at(BASE_MULTIPLIER_BPS)
+    }
+
+    pub fn quote_at(&self, multiplier_bps: u64) -> (Option<Fill>, Option<Fill>) {
+        (
+            self.clone().buy_at(multiplier_bps).ok(),
+            self.clone().sell_at(multiplier_bps).ok(),
+        )
+    }
+
+    // what a route pays for this pool's next nft, None where the route skips the pool.
+    // private, rate limited and proof priced pools only trade through the swap instructions
+    pub fn route_buy_quote(&self) -> Option<u64> {
+        if !self.valid
+            || self.taker_root.is_some()
+            || self.buy_limit != 0
+            || self.priced_leaves
+            || (self.recheck_inventory && self.merkle_root.is_some())
+        {
+            return None;
+        }
+        self.quote().0?.cost().ok()
+    }
+
+    // what a route receives for selling this pool one nft
+    pub fn route_sell_quote(&self) -> Option<u64> {
+        if !self.valid || self.taker_root.is_some() || self.priced_leaves {
+            return None;
+        }
+        self.quote().1?.proceeds().ok()
+    }
+
+    pub fn deposit_nft(&mut self) -> Result<()> {
+        self.deposit_nfts(1)
+    }
+
+    pub fn withdraw_nft(&mut self) -> Result<()> {
+        self.withdraw_nfts(1)
+    }
+
+    // batch deposits and withdrawals move n_nft once, so the curve state is refreshed once
+    pub fn deposit_nfts(&mut self, count: u64) -> Result<()> {
+        self.check_no_flash_loan()?;
+        self.n_nft = self
+            .n_nft
+            .checked_add(count)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.refresh()
+    }
+
+    pub fn withdraw_nfts(&mut self, count: u64) -> Result<()> {
+        self.check_no_flash_loan()?;
+        self.n_nft = self
+            .n_nft
+            .checked_sub(count)
+            .ok_or(ErrorCode::InventoryUnderflow)?;
+        self.refresh()
+    }
+
+    pub fn deposit_tokens(&mut self, amount: u64) -> Result<()> {
+        self.n_token = self
+            .n_token
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.refresh()
+    }
+
+    // accrued fees leave through take_fees so they stay reportable
+    pub fn withdraw_tokens(&mut self, amount: u64) -> Result<()> {
+        if amount > self.n_token {
+            return Err(ErrorCode::NotEnoughBalance.into());
+        }
+        self.n_token -= amount;
+        self.refresh()
+    }
+
+    // nft_mint leaves its vault until repay_nft, n_nft still counts it.
+    // shared pools price lp shares off the vault, so they neither lend nor rent
+    pub fn lend_nft(&mut self, nft_mint: Pubkey) -> Result<()> {
+        self.check_not_shared()?;
+        if self.flash_fee.is_none() {
+            return Err(ErrorCode::FlashLoansDisabled.into());
+        }
+        self.check_no_flash_loan()?;
+        if self.n_nft == 0 {
+            return Err(ErrorCode::InventoryUnderflow.into());
+        }
+        self.flash_loan_mint = Some(nft_mint);
+        Ok(())
+    }
+
+    // returns the fee token_vault receives
+    pub fn repay_nft(&mut self, nft_mint: Pubkey) -> Result<u64> {
+        if self.flash_loan_mint != Some(nft_mint) {
+            return Err(ErrorCode::NoFlashLoan.into());
+        }
+        let fee = self.flash_fee.unwrap_or(0);
+        self.credit_income(fee)?;
+        self.flash_loan_mint = None;
+        Ok(fee)
+    }
+
+    // an nft leaves for a rental of days, returns the fee token_vault receives
+    pub fn rent_nft(&mut self, days: u64) -> Result<u64> {
+        self.check_not_shared()?;
+        let rate = self.rental_rate.ok_or(ErrorCode::RentalsDisabled)?;
+        self.check_no_flash_loan()?;
+        if days == 0 {
+            return Err(ErrorCode::InvalidRentalDuration.into());
+        }
+        let fee = rate.checked_mul(days).ok_or(ErrorCode::MathOverflow)?;
+        let n_nft = self
+            .n_nft
+            .checked_sub(1)
+            .ok_or(ErrorCode::InventoryUnderflow)?;
+        let n_rented = self
+            .n_rented
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        self.credit_income(fee)?;
+        self.n_nft = n_nft;
+        self.n_rented = n_rented;
+        self.refresh()?;
+        Ok(fee)
+    }
+
+    pub fn return_nft(&mut self) -> Result<()> {
+        self.n_rented = self
+            .n_rented
+            .checked_sub(1)
+            .ok_or(ErrorCode::InventoryUnderflow)?;
+        self.n_nft = self.n_nft.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.refresh()
+    }
+
+    // flash loan and rental fees, TRADE pools accrue them as pool fees
+    fn credit_income(&mut self, fee: u64) -> Result<()> {
+        if self.pool_type == 2 {
+            return self.record_pool_fee(fee);
+        }
+        self.n_token = self
+            .n_token
+            .checked_add(fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    fn check_no_flash_loan(&self) -> Result<()> {
+        if self.flash_loan_mint.is_some() {
+            return Err(ErrorCode::FlashLoanActive.into());
+        }
+        Ok(())
+    }
+
+    // zeroes accrued_fees, returns the amount token_vault pays out
+    pub fn take_fees(&mut self) -> u64 {
+        let fees = self.accrued_fees;
+        self.accrued_fees = 0;
+        fees
+    }
+
+    // re-derives pricing state after a liquidity change
+    pub fn refresh(&mut self) -> Result<()> {
+        if self.pool_type != 2 {
+            return Ok(());
+        }
+        if self.curve_type == 3 {
+            self.spot_price = self.xyk_spot_price()?;
+        }
+        Ok(())
+    }
+}
+
+fn apply_multiplier(price: u64, multiplier_bps: u64) -> Result<u64> {
+    if multiplier_bps == 0 {
+        return Err(ErrorCode::InvalidMultiplier.into());
+    }
+    let price = price as u128 * multiplier_bps as u128 / BASE_MULTIPLIER_BPS as u128;
+    u64::try_from(price).map_err(|_| error!(ErrorCode::MathOverflow))
+}
@@ -1,5 +1,18 @@
+use crate::{
+    error::ErrorCode,
+    utils::{
+        assert_delta, assert_nft_mint, assert_spot_price, asset_collection, asset_merkle,
+        asset_priced_merkle,
+    },
+};
 use anchor_lang::prelude::*;
 
+// referral fees frontends may charge on any pool, owners can only lower it
+pub const MAX_REFERRAL_BPS: u64 = 200;
+
+// multiplier of an nft trading at the curve price
+pub const BASE_MULTIPLIER_BPS: u64 = 10000;
+
 #[account]
 #[derive(Default)]
 pub struct Pool {
@@ -13,12 +26,719 @@ pub struct Pool {
     pub fee_bps: u64,                  // 147 -> 8
     pub curator: Pubkey,               // 155 -> 32
     pub curator_fee_bps: u64,          // 187 -> 8
-    // 0=LINEAR, 1=EXPONENTIAL
+    // 0=LINEAR, 1=EXPONENTIAL, 2=DUTCH, 3=XYK, 4=ORACLE
     pub curve_type: u8, // 195 -> 1
     // 0=TOKEN, 1=NFT, 2=TRADE
     pub pool_type: u8,   // 196 -> 1
     pub delta: i64,      // 197 -> 8
     pub spot_price: u64, // 205 -> 8
     pub valid: bool,     // 213 -> 1
-                         // 214 total
+    // DUTCH: spot_price decays by decay_rate per second down to floor_price
+    pub floor_price: u64, // 214 -> 8
+    pub decay_rate: u64,  // 222 -> 8
+    pub last_update: i64, // 230 -> 8
+    // XYK: virtual reserves added to n_token and n_nft when pricing
+    pub virtual_token_reserve: u64, // 238 -> 8
+    pub virtual_nft_reserve: u64,   // 246 -> 8
+    // TRADE: pool fees held in token_vault but excluded from n_token
+    pub accrued_fees: u64, // 254 -> 8
+    pub total_fees: u64,   // 262 -> 8
+    // set once shared liquidity is enabled, liquidity then moves through lp_mint
+    pub lp_mint: Option<Pubkey>, // 270 -> 1+32
+    // decimals of mint, prices and fees are in its base units
+    pub decimals: u8, // 303 -> 1
+    // trade prices and the spot_price they leave stay within these, max_price 0=unbounded
+    pub min_price: u64, // 304 -> 8
+    pub max_price: u64, // 312 -> 8
+    // TRADE: the pool pays sellers spread_bps less than the curve's sell price
+    pub spread_bps: u64, // 320 -> 8
+    // unix timestamp after which the pool no longer trades, 0=never
+    pub expires_at: i64, // 328 -> 8
+    // merkle root over taker wallets allowed to swap, None=anyone
+    pub taker_root: Option<[u8; 32]>, // 336 -> 1+32
+    // nfts one wallet may buy, counted in its BuyerRecord, 0=unlimited
+    pub buy_limit: u64, // 369 -> 8
+    // cap on the referral_bps a swap may pass, 0=MAX_REFERRAL_BPS
+    pub max_referral_bps: u64, // 377 -> 8
+    // fee per flash loan of one nft, None=flash loans disabled
+    pub flash_fee: Option<u64>, // 385 -> 1+8
+    // nft lent out until flash_repay, nft trades are rejected meanwhile
+    pub flash_loan_mint: Option<Pubkey>, // 394 -> 1+32
+    // fee per day an nft is rented out, None=rentals disabled
+    pub rental_rate: Option<u64>, // 427 -> 1+8
+    // nfts out on rental, not counted in n_nft until returned
+    pub n_rented: u64, // 436 -> 8
+    // after a merkle root rotation, buys must prove the nft against the current root
+    pub recheck_inventory: bool, // 444 -> 1
+    // merkle leaves are keccak(mint || multiplier_bps), each nft trades at its multiplier of the curve
+    pub priced_leaves: bool, // 445 -> 1
+    // ORACLE: spot_price is the oracle PriceFeed's price times oracle_bps, synced on every trade
+    pub oracle: Option<Pubkey>, // 446 -> 1+32
+    pub oracle_bps: u64,        // 479 -> 8
+    // trades stop once the synced price was published more than max_staleness seconds ago
+    pub max_staleness: i64,     // 487 -> 8
+    pub oracle_updated_at: i64, // 495 -> 8
+    // inventory pages created so far and the oldest page that may still hold nfts
+    pub inventory_pages: u32, // 503 -> 4
+    pub inventory_head: u32,  // 507 -> 4
+    // page of the collection index the pool is listed on
+    pub index_page: u32, // 511 -> 4
+                         // 515 total
+}
+
+impl Pool {
+    pub fn pool_fee(&self, price: u64) -> Result<u64> {
+        if self.pool_type != 2 {
+            return Ok(0);
+        }
+        Ok(price
+            .checked_mul(self.fee_bps)
+            .ok_or(ErrorCode::MathOverflow)?
+            / 10000)
+    }
+
+    pub fn curator_fee(&self, price: u64) -> Result<u64> {
+        Ok(price
+            .checked_mul(self.curator_fee_bps)
+            .ok_or(ErrorCode::MathOverflow)?
+            / 10000)
+    }
+
+    // price the pool charges for its next nft, before fees
+    pub fn buy_price(&self) -> Result<u64> {
+        if self.curve_type != 3 {
+            return Ok(self.spot_price);
+        }
+        // xyk: cost of taking one nft out of the reserves, x / (y - 1)
+        let (x, y) = self.xyk_reserves();
+        if y <= 1 {
+            return Err(ErrorCode::InvalidSpotPrice.into());
+        }
+        u64::try_from(x / (y - 1)).map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    // price the pool pays for the next nft, before fees
+    pub fn sell_price(&self) -> Result<u64> {
+        let price = if self.curve_type == 3 {
+            // xyk: value of adding one nft to the reserves, x / (y + 1)
+            let (x, y) = self.xyk_reserves();
+            u64::try_from(x / (y + 1)).map_err(|_| ErrorCode::MathOverflow)?
+        } else if self.pool_type == 2 {
+            // TRADE: one step below spot, so buying then selling back returns the same price
+            self.spot_price_after_sell()?
+        } else {
+            self.spot_price
+        };
+        Ok(price
+            .checked_sub(self.spread(price)?)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    // TRADE: amount held back from a seller on top of the curve step
+    pub fn spread(&self, price: u64) -> Result<u64> {
+        if self.pool_type != 2 {
+            return Ok(0);
+        }
+        Ok(price
+            .checked_mul(self.spread_bps)
+            .ok_or(ErrorCode::MathOverflow)?
+            / 10000)
+    }
+
+    // total paid by a buyer for the next nft
+    pub fn buy_quote(&self) -> Result<u64> {
+        let price = self.buy_price()?;
+        let fees = self
+            .pool_fee(price)?
+            .checked_add(self.curator_fee(price)?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(price.checked_add(fees).ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    // net received by a seller for the next nft
+    pub fn sell_quote(&self) -> Result<u64> {
+        let price = self.sell_price()?;
+        Ok(price
+            .saturating_sub(self.pool_fee(price)?)
+            .saturating_sub(self.curator_fee(price)?))
+    }
+
+    // paid by the taker to the referrer on top of the fill, never touches the pool
+    pub fn referral_fee(&self, price: u64, referral_bps: u64) -> Result<u64> {
+        let max_referral_bps = if self.max_referral_bps == 0 {
+            MAX_REFERRAL_BPS
+        } else {
+            self.max_referral_bps
+        };
+        if referral_bps > max_referral_bps {
+            return Err(ErrorCode::ReferralTooHigh.into());
+        }
+        Ok(price
+            .checked_mul(referral_bps)
+            .ok_or(ErrorCode::MathOverflow)?
+            / 10000)
+    }
+
+    pub fn record_pool_fee(&mut self, pool_fee: u64) -> Result<()> {
+        let accrued_fees = self.accrued_fees.checked_add(pool_fee);
+        let total_fees = self.total_fees.checked_add(pool_fee);
+        match (accrued_fees, total_fees) {
+            (Some(accrued_fees), Some(total_fees)) => {
+                self.accrued_fees = accrued_fees;
+                self.total_fees = total_fees;
+                Ok(())
+            }
+            _ => Err(ErrorCode::MathOverflow.into()),
+        }
+    }
+
+    // token_vault holds principal and unwithdrawn fees, n_token is principal only
+    pub fn sync_n_token(&mut self, vault_amount: u64) -> Result<()> {
+        self.n_token = vault_amount
+            .checked_sub(self.accrued_fees)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn check_price_bounds(&self, price: u64) -> Result<()> {
+        if price < self.min_price {
+            return Err(ErrorCode::PriceBelowFloor.into());
+        }
+        if self.max_price != 0 && price > self.max_price {
+            return Err(ErrorCode::PriceAboveCeiling.into());
+        }
+        Ok(())
+    }
+
+    // once lp shares exist the pool's nfts and tokens belong to its depositors, so the owner
+    // can no longer reprice it, gate its takers or lend its nfts out
+    pub fn check_not_shared(&self) -> Result<()> {
+        if self.lp_mint.is_some() {
+            return Err(ErrorCode::SharedPool.into());
+        }
+        Ok(())
+    }
+
+    // oracle pools take price and delta from their feed
+    pub fn edit_delta(&mut self, delta: i64) -> Result<()> {
+        self.check_not_shared()?;
+        if self.curve_type == 4 {
+            return Err(ErrorCode::InvalidCurveType.into());
+        }
+        assert_delta(self.curve_type, delta, self.spot_price)?;
+        self.delta = delta;
+        Ok(())
+    }
+
+    // the current delta has to stay valid at the new price
+    pub fn edit_spot_price(&mut self, spot_price: u64) -> Result<()> {
+        self.check_not_shared()?;
+        if self.curve_type == 4 {
+            return Err(ErrorCode::InvalidCurveType.into());
+        }
+        assert_spot_price(self.curve_type, spot_price)?;
+        assert_delta(self.curve_type, self.delta, spot_price)?;
+        self.check_price_bounds(spot_price)?;
+        self.spot_price = spot_price;
+        Ok(())
+    }
+
+    // quote status: 0=OPEN, 1=AT_FLOOR, 2=AT_CEILING, 3=both
+    // AT_FLOOR when the next trade in some direction would cross min_price, AT_CEILING likewise
+    pub fn price_status(&self) -> u8 {
+        let prices = [
+            self.buy_price(),
+            self.sell_price(),
+            self.spot_price_after_buy(),
+            self.spot_price_after_sell(),
+        ];
+        let at_floor = prices
+            .iter()
+            .any(|p| matches!(p, Ok(p) if *p < self.min_price));
+        let at_ceiling = prices
+            .iter()
+            .any(|p| matches!(p, Ok(p) if self.max_price != 0 && *p > self.max_price));
+        at_floor as u8 | (at_ceiling as u8) << 1
+    }
+
+    // once the head page drains, the next page holds the oldest nfts
+    pub fn advance_inventory_head(&mut self, inventory: &Inventory) -> bool {
+        if inventory.page != self.inventory_head
+            || !inventory.mints.is_empty()
+            || self.inventory_head + 1 >= self.inventory_pages
+        {
+            return false;
+        }
+        self.inventory_head += 1;
+        true
+    }
+
+    // seed of the collection index this pool is listed in
+    pub fn index_key(&self) -> [u8; 32] {
+        if let Some(collection) = self.collection {
+            collection.to_bytes()
+        } else {
+            self.merkle_root.unwrap_or_default()
+        }
+    }
+
+    pub fn is_indexed_in(&self, collection_index: &CollectionIndex) -> bool {
+        collection_index.key == self.index_key() && collection_index.page == self.index_page
+    }
+
+    // multiplier an nft trades at, checking its leaf against the current root.
+    // multiplier_bps is ignored unless the pool uses priced leaves
+    pub fn check_nft_proof(
+        &self,
+        mint: Pubkey,
+        multiplier_bps: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<u64> {
+        let merkle_root = match self.merkle_root {
+            Some(merkle_root) => merkle_root,
+            None => return Ok(BASE_MULTIPLIER_BPS),
+        };
+        if self.priced_leaves {
+            asset_priced_merkle(mint, multiplier_bps, merkle_root, proof)?;
+            Ok(multiplier_bps)
+        } else {
+            asset_merkle(mint, merkle_root, proof)?;
+            Ok(BASE_MULTIPLIER_BPS)
+        }
+    }
+
+    // buys only prove the nft when its leaf sets the price, or when the owner rotated the
+    // root and asked for inventory deposited under the old one to stop selling
+    pub fn check_inventory_proof(
+        &self,
+        mint: Pubkey,
+        multiplier_bps: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<u64> {
+        if self.recheck_inventory || self.priced_leaves {
+            self.check_nft_proof(mint, multiplier_bps, proof)
+        } else {
+            Ok(BASE_MULTIPLIER_BPS)
+        }
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+
+    // liquidity can still be withdrawn from an expired pool, only trades stop
+    pub fn check_expiry(&self, now: i64) -> Result<()> {
+        if self.is_expired(now) {
+            return Err(ErrorCode::PoolExpired.into());
+        }
+        Ok(())
+    }
+
+    // ORACLE: peg spot_price to a feed price published at updated_at. a price outside the
+    // owner's bounds is rejected, so the pool goes stale instead of trading there
+    pub fn sync_oracle(&mut self, price: u64, updated_at: i64) -> Result<()> {
+        if self.curve_type != 4 {
+            return Err(ErrorCode::InvalidCurveType.into());
+        }
+        let spot_price = u128::from(price) * u128::from(self.oracle_bps) / 10000;
+        let spot_price = u64::try_from(spot_price).map_err(|_| ErrorCode::MathOverflow)?;
+        self.check_price_bounds(spot_price)?;
+        self.spot_price = spot_price;
+        self.oracle_updated_at = updated_at;
+        Ok(())
+    }
+
+    // ORACLE: reprice from the pool's PriceFeed, taken off the front of remaining_accounts, so
+    // trades price off the feed itself rather than the last sync_oracle. other curves take nothing
+    pub fn sync_price_feed<'a, 'info: 'a>(
+        &mut self,
+        remaining_accounts: &mut impl Iterator<Item = &'a AccountInfo<'info>>,
+    ) -> Result<()> {
+        if self.curve_type != 4 {
+            return Ok(());
+        }
+        let info = remaining_accounts
+            .next()
+            .ok_or(ErrorCode::MissingPriceFeed)?;
+        if self.oracle != Some(info.key()) {
+            return Err(ErrorCode::PublicKeyMismatch.into());
+        }
+        let price_feed = Account::<PriceFeed>::try_from(info)?;
+        self.sync_oracle(price_feed.price, price_feed.updated_at)
+    }
+
+    pub fn check_oracle(&self, now: i64) -> Result<()> {
+        if self.curve_type != 4 {
+            return Ok(());
+        }
+        if self.oracle.is_none() || now.saturating_sub(self.oracle_updated_at) > self.max_staleness
+        {
+            return Err(ErrorCode::StaleOracle.into());
+        }
+        Ok(())
+    }
+
+    // bring a dutch auction's spot_price up to date before it is traded against
+    pub fn apply_decay(&mut self, now: i64) {
+        if self.curve_type == 2 && self.spot_price > self.floor_price {
+            let elapsed = now.saturating_sub(self.last_update).max(0) as u64;
+            let decay = self.decay_rate.saturating_mul(elapsed);
+            self.spot_price = self
+                .spot_price
+                .saturating_sub(decay)
+                .max(self.floor_price)
+                .max(self.min_price);
+        }
+        self.last_update = now;
+    }
+
+    // xyk: real inventory plus the virtual reserves set at init
+    pub fn xyk_reserves(&self) -> (u128, u128) {
+        (
+            self.n_token as u128 + self.virtual_token_reserve as u128,
+            self.n_nft as u128 + self.virtual_nft_reserve as u128,
+        )
+    }
+
+    // xyk: mid price of the current reserves
+    pub fn xyk_spot_price(&self) -> Result<u64> {
+        let (x, y) = self.xyk_reserves();
+        if y == 0 {
+            return Err(ErrorCode::InvalidSpotPrice.into());
+        }
+        u64::try_from(x / y).map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    fn signed_spot_price(&self) -> Result<i64> {
+        i64::try_from(self.spot_price).map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    pub fn spot_price_after_buy(&self) -> Result<u64> {
+        let new_spot_price = if self.curve_type == 0 || self.curve_type == 2 {
+            // linear curve, dutch auctions step linearly on trades
+            self.signed_spot_price()?
+                .checked_add(self.delta)
+                .ok_or(ErrorCode::MathOverflow)?
+        } else if self.curve_type == 4 {
+            // oracle curve, only sync_oracle moves the price
+            self.signed_spot_price()?
+        } else if self.curve_type == 3 {
+            // xyk curve, mid price once the nft has left and its price is in
+            let (x, y) = self.xyk_reserves();
+            let x = x + self.buy_price()? as u128;
+            i64::try_from(x / (y - 1)).map_err(|_| ErrorCode::MathOverflow)?
+        } else {
+            // exponential curve
+            let multiplier = 100i64
+                .checked_add(self.delta)
+                .ok_or(ErrorCode::MathOverflow)?;
+            self.signed_spot_price()?
+                .checked_mul(multiplier)
+                .ok_or(ErrorCode::MathOverflow)?
+                / 100
+        };
+        if new_spot_price < 0 {
+            return Err(ErrorCode::InvalidSpotPrice.into());
+        }
+        Ok(new_spot_price as u64)
+    }
+
+    pub fn spot_price_after_sell(&self) -> Result<u64> {
+        let new_spot_price = if self.curve_type == 0 {
+            // linear curve
+            self.signed_spot_price()?
+                .checked_sub(self.delta)
+                .ok_or(ErrorCode::MathOverflow)?
+        } else if self.curve_type == 2 {
+            // dutch auctions step linearly on trades, but never below their floor
+            let floor_price =
+                i64::try_from(self.floor_price).map_err(|_| ErrorCode::MathOverflow)?;
+            self.signed_spot_price()?
+                .checked_sub(self.delta)
+                .ok_or(ErrorCode::MathOverflow)?
+                .max(floor_price)
+        } else if self.curve_type == 4 {
+            // oracle curve, only sync_oracle moves the price
+            self.signed_spot_price()?
+        } else if self.curve_type == 3 {
+            // xyk curve, mid price once the nft is in and its price has left
+            let (x, y) = self.xyk_reserves();
+            let x = x - self.sell_price()? as u128;
+            i64::try_from(x / (y + 1)).map_err(|_| ErrorCode::MathOverflow)?
+        } else {
+            // exponential curve
+            let multiplier = 100i64
+                .checked_sub(self.delta)
+                .ok_or(ErrorCode::MathOverflow)?;
+            self.signed_spot_price()?
+                .checked_mul(multiplier)
+                .ok_or(ErrorCode::MathOverflow)?
+                / 100
+        };
+        if new_spot_price < 0 {
+            return Err(ErrorCode::InvalidSpotPrice.into());
+        }
+        Ok(new_spot_price as u64)
+    }
+}
+
+// arc-owned price for oracle pools, written by its authority in base units of the pool mint
+#[account]
+#[derive(Default)]
+pub struct PriceFeed {
+    pub authority: Pubkey, // 0 -> 32
+    pub price: u64,        // 32 -> 8
+    pub updated_at: i64,   // 40 -> 8
+}
+
+impl PriceFeed {
+    pub const SPACE: usize = 8 + 48;
+}
+
+#[account]
+#[derive(Default)]
+pub struct Bid {
+    pub bidder: Pubkey,                // 0 -> 32
+    pub collection: Option<Pubkey>,    // 32 -> 1+32
+    pub merkle_root: Option<[u8; 32]>, // 65 -> 1+32
+    pub mint: Pubkey,                  // 98 -> 32
+    pub price: u64,                    // 130 -> 8
+    pub quantity: u64,                 // 138 -> 8
+    // unix timestamp, 0=never
+    pub expiry: i64, // 146 -> 8
+    pub bid_nonce: u8, // 154 -> 1
+                     // 155 total
+}
+
+impl Bid {
+    // a bid is for nfts of some collection or tree, and has to be fillable when placed
+    pub fn check_terms(&self, now: i64) -> Result<()> {
+        if self.price == 0 {
+            return Err(ErrorCode::InvalidSpotPrice.into());
+        }
+        if self.quantity == 0 {
+            return Err(ErrorCode::InvalidQuantity.into());
+        }
+        if self.collection.is_none() && self.merkle_root.is_none() {
+            return Err(ErrorCode::BidWithoutFilter.into());
+        }
+        if self.is_expired(now) {
+            return Err(ErrorCode::InvalidBidExpiry.into());
+        }
+        Ok(())
+    }
+
+    // escrowed at placement, one price per unit still open
+    pub fn escrow_amount(&self) -> Result<u64> {
+        Ok(self
+            .price
+            .checked_mul(self.quantity)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expiry != 0 && now >= self.expiry
+    }
+
+    // the seller's nft must be a real nft matching the bid's collection and merkle root
+    pub fn check_nft(
+        &self,
+        nft_mint: &AccountInfo,
+        nft_mint_metadata: &AccountInfo,
+        merkle_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        assert_nft_mint(nft_mint)?;
+        if let Some(collection) = self.collection {
+            asset_collection(nft_mint, nft_mint_metadata, collection)?;
+        }
+        if let Some(merkle_root) = self.merkle_root {
+            asset_merkle(nft_mint.key(), merkle_root, merkle_proof)?;
+        }
+        Ok(())
+    }
+
+    // fills one unit, returns what escrow pays the seller
+    pub fn fill(&mut self, now: i64) -> Result<u64> {
+        if self.quantity == 0 {
+            return Err(ErrorCode::BidFilled.into());
+        }
+        if self.is_expired(now) {
+            return Err(ErrorCode::BidExpired.into());
+        }
+        self.quantity -= 1;
+        Ok(self.price)
+    }
+}
+
+// nfts a wallet has bought from a pool with a buy_limit
+#[account]
+#[derive(Default)]
+pub struct BuyerRecord {
+    pub pool: Pubkey,  // 0 -> 32
+    pub buyer: Pubkey, // 32 -> 32
+    pub bought: u64,   // 64 -> 8
+                       // 72 total
+}
+
+impl BuyerRecord {
+    pub const SPACE: usize = 8 + 72;
+
+    pub fn record_buy(&mut self, buy_limit: u64) -> Result<()> {
+        if self.bought >= buy_limit {
+            return Err(ErrorCode::BuyLimitReached.into());
+        }
+        self.bought += 1;
+        Ok(())
+    }
+}
+
+// one rented nft, frozen in renter_nft_ata with pool_auth as its delegate
+#[account]
+#[derive(Default)]
+pub struct Rental {
+    pub pool: Pubkey,           // 0 -> 32
+    pub nft_mint: Pubkey,       // 32 -> 32
+    pub renter: Pubkey,         // 64 -> 32
+    pub renter_nft_ata: Pubkey, // 96 -> 32
+    pub expires_at: i64,        // 128 -> 8
+                                // 136 total
+}
+
+impl Rental {
+    pub const SPACE: usize = 8 + 136;
+}
+
+// mints per inventory page, keeps each page under the 10kb init limit
+pub const INVENTORY_CAPACITY: usize = 256;
+
+// one page of the mints a pool holds, at [b"inventory", pool, page as u32 le].
+// nfts only go on the newest page, so lower pages hold older nfts
+#[account]
+#[derive(Default)]
+pub struct Inventory {
+    pub pool: Pubkey,       // 0 -> 32
+    pub page: u32,          // 32 -> 4
+    pub mints: Vec<Pubkey>, // 36 -> 4 + 32*INVENTORY_CAPACITY
+}
+
+impl Inventory {
+    pub const SPACE: usize = 8 + 32 + 4 + 4 + 32 * INVENTORY_CAPACITY;
+
+    pub fn is_full(&self) -> bool {
+        self.mints.len() >= INVENTORY_CAPACITY
+    }
+
+    pub fn insert(&mut self, pool: &Pool, mint: Pubkey) -> Result<()> {
+        if self.page + 1 != pool.inventory_pages {
+            return Err(ErrorCode::WrongInventoryPage.into());
+        }
+        if self.is_full() {
+            return Err(ErrorCode::InventoryFull.into());
+        }
+        self.mints.push(mint);
+        Ok(())
+    }
+
+    // keeps deposit order so the oldest nft stays first
+    pub fn remove(&mut self, pool: &mut Pool, mint: Pubkey) -> Result<()> {
+        let idx = self
+            .mints
+            .iter()
+            .position(|m| *m == mint)
+            .ok_or(ErrorCode::NotInInventory)?;
+        self.mints.remove(idx);
+        pool.advance_inventory_head(self);
+        Ok(())
+    }
+
+    // the pool's oldest nft, first on its head page
+    pub fn oldest(&self, pool: &Pool) -> Result<Pubkey> {
+        if self.page != pool.inventory_head {
+            return Err(ErrorCode::WrongInventoryPage.into());
+        }
+        self.mints
+            .first()
+            .copied()
+            .ok_or_else(|| ErrorCode::InventoryUnderflow.into())
+    }
+
+    // the nft buy_any delivers, with its multiplier. once a rotated root is rechecked, nfts
+    // deposited under the old root can no longer sell, so the first nft on this page that
+    // proves is taken instead, from any page since stale nfts may fill the head
+    pub fn oldest_provable(
+        &self,
+        pool: &Pool,
+        multiplier_bps: u64,
+        proof: &[[u8; 32]],
+    ) -> Result<(Pubkey, u64)> {
+        if !pool.recheck_inventory {
+            let mint = self.oldest(pool)?;
+            return Ok((
+                mint,
+                pool.check_inventory_proof(mint, multiplier_bps, proof.to_vec())?,
+            ));
+        }
+        self.mints
+            .iter()
+            .find_map(|mint| {
+                pool.check_inventory_proof(*mint, multiplier_bps, proof.to_vec())
+                    .ok()
+                    .map(|multiplier_bps| (*mint, multiplier_bps))
+            })
+            .ok_or_else(|| ErrorCode::InvalidProof.into())
+    }
+}
+
+// max pools listed per collection index page
+pub const COLLECTION_INDEX_CAPACITY: usize = 200;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PoolEntry {
+    pub pool: Pubkey,    // 0 -> 32
+    pub spot_price: u64, // 32 -> 8
+}
+
+// one page of the pools listed under a key, at [b"collection_index", key, page as u32 le].
+// pages are opened in order once the previous page fills, so readers scan from page 0
+// until a page is missing
+#[account]
+#[derive(Default)]
+pub struct CollectionIndex {
+    // collection key, else merkle root, else zeroes for unrestricted pools
+    pub key: [u8; 32],         // 0 -> 32
+    pub page: u32,             // 32 -> 4
+    pub pools: Vec<PoolEntry>, // 36 -> 4 + 40*COLLECTION_INDEX_CAPACITY
+}
+
+impl CollectionIndex {
+    pub const SPACE: usize = 8 + 32 + 4 + 4 + 40 * COLLECTION_INDEX_CAPACITY;
+
+    pub fn is_full(&self) -> bool {
+        self.pools.len() >= COLLECTION_INDEX_CAPACITY
+    }
+
+    pub fn register(&mut self, pool: Pubkey, spot_price: u64) -> Result<()> {
+        if self.is_full() {
+            return Err(ErrorCode::CollectionIndexFull.into());
+        }
+        self.pools.push(PoolEntry { pool, spot_price });
+        Ok(())
+    }
+
+    pub fn deregister(&mut self, pool: Pubkey) -> Result<()> {
+        let idx = self
+            .pools
+            .iter()
+            .position(|e| e.pool == pool)
+            .ok_or(ErrorCode::NotInCollectionIndex)?;
+        self.pools.swap_remove(idx);
+        Ok(())
+    }
+
+    // spot prices are refreshed on trades, not on liquidity changes
+    pub fn update_spot_price(&mut self, pool: Pubkey, spot_price: u64) {
+        if let Some(entry) = self.pools.iter_mut().find(|e| e.pool == pool) {
+            entry.spot_price = spot_price;
+        }
+    }
 }
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::Pool};
+
+// conversions between human units ("1.25") and the base units pools store,
+// for clients building instructions and reading quotes
+
+// digits past the mint's precision are only accepted when they are zero,
+// so a price is never silently rounded
+pub fn parse_units(amount: &str, decimals: u8) -> Result<u64> {
+    let (whole, frac) = amount.split_once('.').unwrap_or((amount, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && frac.is_empty()) || !is_digits(whole) || !is_digits(frac) {
+        return Err(ErrorCode::InvalidAmount.into());
+    }
+
+    let decimals = decimals as usize;
+    let (frac, dropped) = frac.split_at(frac.len().min(decimals));
+    if dropped.bytes().any(|b| b != b'0') {
+        return Err(ErrorCode::PriceBelowPrecision.into());
+    }
+
+    let scale = 10u64
+        .checked_pow(decimals as u32)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let whole = if whole.is_empty() {
+        0
+    } else {
+        whole.parse::<u64>().map_err(|_| ErrorCode::MathOverflow)?
+    };
+    let frac = if frac.is_empty() {
+        0
+    } else {
+        // "5" at 2 decimals is 50 base units
+        frac.parse::<u64>().map_err(|_| ErrorCode::MathOverflow)?
+            * 10u64.pow((decimals - frac.len()) as u32)
+    };
+    Ok(whole
+        .checked_mul(scale)
+        .and_then(|w| w.checked_add(frac))
+        .ok_or(ErrorCode::MathOverflow)?)
+}
+
+pub fn format_units(amount: u64, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let digits = format!("{:0>width$}", amount, width = decimals + 1);
+    let (whole, frac) = digits.split_at(digits.len() - decimals);
+    let frac = frac.trim_end_matches('0');
+    if frac.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, frac)
+    }
+}
+
+impl Pool {
+    // a price in human units of mint, zero after conversion is rejected
+    pub fn parse_price(&self, price: &str) -> Result<u64> {
+        let price = parse_units(price, self.decimals)?;
+        if price == 0 {
+            return Err(ErrorCode::PriceBelowPrecision.into());
+        }
+        Ok(price)
+    }
+
+    pub fn format_price(&self, price: u64) -> String {
+        format_units(price, self.decimals)
+    }
+
+    pub fn buy_quote_ui(&self) -> Result<String> {
+        Ok(self.format_price(self.buy_quote()?))
+    }
+
+    pub fn sell_quote_ui(&self) -> Result<String> {
+        Ok(self.format_price(self.sell_quote()?))
+    }
+}
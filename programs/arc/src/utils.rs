@@ -5,17 +5,15 @@ use {
 
     anchor_lang::{
         prelude::*,
-        solana_program::program_pack::{IsInitialized, Pack},
-    },
-    // anchor_spl::token::{Mint, Token, TokenAccount},
-    anchor_spl::{
-        associated_token::{self},
-        token::{self, TokenAccount},
+        solana_program::{
+            program_pack::{IsInitialized, Pack},
+            sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+        },
+        InstructionData,
     },
     mpl_token_metadata::state::Metadata,
     spl_associated_token_account::get_associated_token_address,
     spl_token::state::Account,
-    std::slice::Iter,
 };
 
 pub fn assert_is_ata(ata: &AccountInfo, wallet: &Pubkey, mint: &Pubkey) -> Result<Account> {
@@ -52,15 +50,12 @@ pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> Result<()> {
     }
 }
 
-pub fn asset_collection<'a>(
+pub fn asset_collection(
     nft_mint: &AccountInfo,
     mint_metadata: &AccountInfo,
     vault_collection: Pubkey,
 ) -> Result<bool> {
-    if !nft_mint.data_is_empty() {
-        return Ok(false);
-    }
-    let (expected_metadata_key, expected_metadata_bump) = Pubkey::find_program_address(
+    let (expected_metadata_key, _) = Pubkey::find_program_address(
         &[
             mpl_token_metadata::state::PREFIX.as_bytes(),
             mpl_token_metadata::id().as_ref(),
@@ -71,28 +66,99 @@ pub fn asset_collection<'a>(
     if expected_metadata_key != mint_metadata.key() {
         return Err(ErrorCode::InvalidMetadataAccount.into());
     }
-    let metadata_attempt = Metadata::from_account_info(mint_metadata);
-    if metadata_attempt.is_err() {
+    let metadata: Metadata = Metadata::from_account_info(mint_metadata)
+        .map_err(|_| ErrorCode::InvalidMetadataAccount)?;
+    if metadata.mint != nft_mint.key() {
         return Err(ErrorCode::InvalidMetadataAccount.into());
     }
-    let metadata: Metadata = metadata_attempt.unwrap();
-    if metadata.collection.is_none() {
+    // anyone can name a collection, only its update authority can verify it
+    let collection = metadata
+        .collection
+        .ok_or(ErrorCode::InvalidMetadataAccount)?;
+    if !collection.verified || collection.key != vault_collection {
         return Err(ErrorCode::InvalidMetadataAccount.into());
-    } else {
-        let collection = metadata.collection.unwrap();
-        if collection.key != vault_collection {
-            return Err(ErrorCode::InvalidMetadataAccount.into());
-        }
-        Ok(true)
     }
+    Ok(true)
+}
+
+// a single token with no decimals
+pub fn assert_nft_mint(nft_mint: &AccountInfo) -> Result<()> {
+    assert_owned_by(nft_mint, &spl_token::id())?;
+    let mint: spl_token::state::Mint = assert_initialized(nft_mint)?;
+    if mint.decimals != 0 || mint.supply != 1 {
+        return Err(ErrorCode::InvalidNftMint.into());
+    }
+    Ok(())
 }
 
-pub fn asset_merkle<'a>(mint: Pubkey, root: [u8; 32], proof: Vec<[u8; 32]>) -> Result<bool> {
-    let node = anchor_lang::solana_program::keccak::hashv(&[&mint.to_bytes()]);
-    require!(verify(proof, root, node.0), ErrorCode::InvalidProof);
+pub fn asset_merkle(mint: Pubkey, root: [u8; 32], proof: Vec<[u8; 32]>) -> Result<bool> {
+    require!(verify(proof, root, nft_leaf(mint)), ErrorCode::InvalidProof);
     Ok(true)
 }
 
+pub fn asset_priced_merkle(
+    mint: Pubkey,
+    multiplier_bps: u64,
+    root: [u8; 32],
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    require!(
+        verify(proof, root, priced_leaf(mint, multiplier_bps)),
+        ErrorCode::InvalidProof
+    );
+    Ok(())
+}
+
+// leaves tree builders emit, keccak(mint) or keccak(mint || multiplier_bps as u64 le) for priced pools
+pub fn nft_leaf(mint: Pubkey) -> [u8; 32] {
+    anchor_lang::solana_program::keccak::hashv(&[&mint.to_bytes()]).0
+}
+
+pub fn priced_leaf(mint: Pubkey, multiplier_bps: u64) -> [u8; 32] {
+    anchor_lang::solana_program::keccak::hashv(&[&mint.to_bytes(), &multiplier_bps.to_le_bytes()]).0
+}
+
+// taker allowlist leaves, keccak(b"taker" || taker). the tag keeps a wallet leaf from ever
+// matching an nft leaf, so one tree cannot pass as both an allowlist and an nft root
+pub fn taker_leaf(taker: Pubkey) -> [u8; 32] {
+    anchor_lang::solana_program::keccak::hashv(&[b"taker", &taker.to_bytes()]).0
+}
+
+pub fn assert_taker(taker: Pubkey, root: [u8; 32], proof: Vec<[u8; 32]>) -> Result<()> {
+    require!(
+        verify(proof, root, taker_leaf(taker)),
+        ErrorCode::TakerNotAllowed
+    );
+    Ok(())
+}
+
+// scans the instructions after the current one for a flash_repay naming the same pool,
+// nft_mint and borrower, accounts 0, 2 and 6 of FlashRepay
+pub fn assert_repay_follows(
+    instructions: &AccountInfo,
+    pool: Pubkey,
+    nft_mint: Pubkey,
+    borrower: Pubkey,
+) -> Result<()> {
+    let repay = crate::instruction::FlashRepay {}.data();
+    let mut idx = (load_current_index_checked(instructions)? as usize)
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    while let Ok(ix) = load_instruction_at_checked(idx, instructions) {
+        let account = |i: usize| ix.accounts.get(i).map(|a| a.pubkey);
+        if ix.program_id == crate::ID
+            && ix.data.get(..8) == Some(&repay[..])
+            && account(0) == Some(pool)
+            && account(2) == Some(nft_mint)
+            && account(6) == Some(borrower)
+        {
+            return Ok(());
+        }
+        idx = idx.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+    }
+    Err(ErrorCode::FlashRepayMissing.into())
+}
+
 pub fn verify(proof: Vec<[u8; 32]>, root: [u8; 32], leaf: [u8; 32]) -> bool {
     let mut computed_hash = leaf;
     for proof_element in proof.into_iter() {
@@ -111,7 +177,7 @@ pub fn verify(proof: Vec<[u8; 32]>, root: [u8; 32], leaf: [u8; 32]) -> bool {
 }
 
 pub fn assert_curve_type(curve_type: u8) -> Result<()> {
-    if !(curve_type == 0 || curve_type == 1) {
+    if curve_type > 4 {
         Err(error!(ErrorCode::InvalidCurveType))
     } else {
         Ok(())
@@ -126,10 +192,53 @@ pub fn asset_pool_type(pool_type: u8) -> Result<()> {
     }
 }
 
-pub fn assert_delta(delta: i64, spot_price: u64) -> Result<()> {
-    if (delta.abs() as u64) > spot_price {
-        Err(error!(ErrorCode::InvalidDelta))
+pub fn assert_delta(curve_type: u8, delta: i64, spot_price: u64) -> Result<()> {
+    let step = delta.unsigned_abs();
+    if curve_type == 1 {
+        // exponential delta is a percentage, each step has to move at least one base unit
+        if step >= 100 || (delta != 0 && spot_price as u128 * (step as u128) < 100) {
+            return Err(error!(ErrorCode::InvalidDelta));
+        }
+    } else if curve_type == 4 && delta != 0 {
+        // oracle prices do not step with trades
+        return Err(error!(ErrorCode::InvalidDelta));
+    } else if curve_type != 3 && step > spot_price {
+        return Err(error!(ErrorCode::InvalidDelta));
+    }
+    Ok(())
+}
+
+pub fn assert_spot_price(curve_type: u8, spot_price: u64) -> Result<()> {
+    // xyk derives spot_price from its reserves, oracle pools from their feed
+    if curve_type != 3 && curve_type != 4 && spot_price == 0 {
+        Err(error!(ErrorCode::PriceBelowPrecision))
     } else {
         Ok(())
     }
 }
+
+pub fn assert_floor_price(curve_type: u8, floor_price: u64, spot_price: u64) -> Result<()> {
+    if curve_type == 2 && floor_price > spot_price {
+        Err(error!(ErrorCode::InvalidSpotPrice))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn assert_xyk_reserves(
+    curve_type: u8,
+    pool_type: u8,
+    virtual_token_reserve: u64,
+    virtual_nft_reserve: u64,
+) -> Result<()> {
+    if curve_type != 3 {
+        return Ok(());
+    }
+    if pool_type != 2 {
+        return Err(error!(ErrorCode::InvalidPoolType));
+    }
+    if virtual_token_reserve == 0 || virtual_nft_reserve == 0 {
+        return Err(error!(ErrorCode::InvalidSpotPrice));
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::keccak::hashv;
+use arc::{
+    state::{Pool, BASE_MULTIPLIER_BPS},
+    utils::{assert_taker, nft_leaf, priced_leaf, taker_leaf},
+};
+
+// parent of two nodes under the sorted-pair scheme utils::verify expects
+fn parent(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[&a, &b]).0
+    } else {
+        hashv(&[&b, &a]).0
+    }
+}
+
+#[test]
+fn allowlisted_takers_pass() {
+    let takers: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let leaves: Vec<[u8; 32]> = takers.iter().copied().map(taker_leaf).collect();
+    let left = parent(leaves[0], leaves[1]);
+    let right = parent(leaves[2], leaves[3]);
+    let root = parent(left, right);
+
+    assert!(assert_taker(takers[0], root, vec![leaves[1], right]).is_ok());
+    assert!(assert_taker(takers[3], root, vec![leaves[2], left]).is_ok());
+
+    // a proof for someone else, or no proof at all, is rejected
+    assert!(assert_taker(Pubkey::new_unique(), root, vec![leaves[1], right]).is_err());
+    assert!(assert_taker(takers[0], root, vec![]).is_err());
+}
+
+#[test]
+fn nft_roots_do_not_pass_as_allowlists() {
+    let taker = Pubkey::new_unique();
+    // a single-leaf tree is its own root
+    assert!(assert_taker(taker, taker_leaf(taker), vec![]).is_ok());
+    assert!(assert_taker(taker, nft_leaf(taker), vec![]).is_err());
+}
+
+#[test]
+fn rotated_root_rechecks_inventory() {
+    let old: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+    let new = Pubkey::new_unique();
+    // rotated from the root over old to a single-leaf tree, which is its own root
+    let mut pool = Pool {
+        merkle_root: Some(nft_leaf(new)),
+        ..Pool::default()
+    };
+    assert!(pool
+        .check_inventory_proof(old[0], BASE_MULTIPLIER_BPS, vec![])
+        .is_ok());
+
+    pool.recheck_inventory = true;
+    assert!(pool
+        .check_inventory_proof(old[0], BASE_MULTIPLIER_BPS, vec![nft_leaf(old[1])])
+        .is_err());
+    assert!(pool
+        .check_inventory_proof(new, BASE_MULTIPLIER_BPS, vec![])
+        .is_ok());
+
+    // clearing the root lifts the check
+    pool.merkle_root = None;
+    assert!(pool
+        .check_inventory_proof(old[0], BASE_MULTIPLIER_BPS, vec![])
+        .is_ok());
+}
+
+#[test]
+fn priced_leaves_prove_their_multiplier() {
+    let common = Pubkey::new_unique();
+    let rare = Pubkey::new_unique();
+    let common_leaf = priced_leaf(common, BASE_MULTIPLIER_BPS);
+    let rare_leaf = priced_leaf(rare, 25_000);
+    let pool = Pool {
+        merkle_root: Some(parent(common_leaf, rare_leaf)),
+        priced_leaves: true,
+        ..Pool::default()
+    };
+
+    assert_eq!(
+        pool.check_nft_proof(rare, 25_000, vec![common_leaf])
+            .unwrap(),
+        25_000
+    );
+    // claiming another multiplier, or the plain leaf, fails
+    assert!(pool
+        .check_nft_proof(rare, 10_000, vec![common_leaf])
+        .is_err());
+    assert!(pool
+        .check_nft_proof(common, 25_000, vec![rare_leaf])
+        .is_err());
+    // buys of priced pools always prove, so a buyer cannot skip to the base price
+    assert!(pool.check_inventory_proof(rare, 10_000, vec![]).is_err());
+}
@@ -0,0 +1,156 @@
+use anchor_lang::{
+    prelude::{AccountInfo, Pubkey},
+    solana_program::program_pack::Pack,
+    AnchorSerialize,
+};
+use arc::state::Bid;
+use mpl_token_metadata::state::{Collection, Key, Metadata, MAX_METADATA_LEN};
+
+// mint and metadata accounts as the token and metadata programs lay them out
+struct Nft {
+    mint: Pubkey,
+    mint_data: Vec<u8>,
+    mint_lamports: u64,
+    metadata: Pubkey,
+    metadata_data: Vec<u8>,
+    metadata_lamports: u64,
+}
+
+impl Nft {
+    fn new(collection: Pubkey, verified: bool, supply: u64) -> Nft {
+        let mint = Pubkey::new_unique();
+        let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            supply,
+            decimals: 0,
+            is_initialized: true,
+            ..spl_token::state::Mint::default()
+        }
+        .pack_into_slice(&mut mint_data);
+
+        let (metadata, _) = Pubkey::find_program_address(
+            &[
+                mpl_token_metadata::state::PREFIX.as_bytes(),
+                mpl_token_metadata::id().as_ref(),
+                mint.as_ref(),
+            ],
+            &mpl_token_metadata::id(),
+        );
+        let mut metadata_data = Metadata {
+            key: Key::MetadataV1,
+            mint,
+            collection: Some(Collection {
+                verified,
+                key: collection,
+            }),
+            ..Metadata::default()
+        }
+        .try_to_vec()
+        .unwrap();
+        metadata_data.resize(MAX_METADATA_LEN, 0);
+
+        Nft {
+            mint,
+            mint_data,
+            mint_lamports: 0,
+            metadata,
+            metadata_data,
+            metadata_lamports: 0,
+        }
+    }
+
+    fn check(&mut self, bid: &Bid) -> bool {
+        let token_program = spl_token::id();
+        let metadata_program = mpl_token_metadata::id();
+        let mint = AccountInfo::new(
+            &self.mint,
+            false,
+            false,
+            &mut self.mint_lamports,
+            &mut self.mint_data,
+            &token_program,
+            false,
+            0,
+        );
+        let metadata = AccountInfo::new(
+            &self.metadata,
+            false,
+            false,
+            &mut self.metadata_lamports,
+            &mut self.metadata_data,
+            &metadata_program,
+            false,
+            0,
+        );
+        bid.check_nft(&mint, &metadata, vec![]).is_ok()
+    }
+}
+
+fn bid(collection: Pubkey) -> Bid {
+    Bid {
+        collection: Some(collection),
+        price: 100,
+        quantity: 3,
+        expiry: 1_000,
+        ..Bid::default()
+    }
+}
+
+#[test]
+fn bid_only_takes_its_collection() {
+    let collection = Pubkey::new_unique();
+    let bid = bid(collection);
+    assert!(Nft::new(collection, true, 1).check(&bid));
+
+    assert!(!Nft::new(Pubkey::new_unique(), true, 1).check(&bid));
+    // named but never verified by the collection authority
+    assert!(!Nft::new(collection, false, 1).check(&bid));
+    // fungible mints are not nfts even with the right metadata
+    assert!(!Nft::new(collection, true, 2).check(&bid));
+}
+
+#[test]
+fn fills_draw_down_the_escrow() {
+    let mut bid = bid(Pubkey::new_unique());
+    let mut escrow = bid.escrow_amount().unwrap();
+    assert_eq!(escrow, 300);
+
+    escrow -= bid.fill(10).unwrap();
+    escrow -= bid.fill(20).unwrap();
+    assert_eq!(bid.quantity, 1);
+    // cancel refunds whatever is left for the open unit
+    assert_eq!(escrow, bid.escrow_amount().unwrap());
+
+    escrow -= bid.fill(30).unwrap();
+    assert_eq!(escrow, 0);
+    assert!(bid.fill(40).is_err());
+}
+
+#[test]
+fn expired_bid_rejects_fills() {
+    let mut bid = bid(Pubkey::new_unique());
+    assert!(bid.fill(999).is_ok());
+    assert!(bid.fill(1_000).is_err());
+    assert_eq!(bid.quantity, 2);
+
+    bid.expiry = 0;
+    assert!(bid.fill(i64::MAX).is_ok());
+}
+
+#[test]
+fn bids_need_a_filter_and_a_future_expiry() {
+    let mut bid = bid(Pubkey::new_unique());
+    assert!(bid.check_terms(999).is_ok());
+    // placed after its own expiry, the escrow could never fill
+    assert!(bid.check_terms(1_000).is_err());
+
+    bid.collection = None;
+    assert!(bid.check_terms(0).is_err());
+    bid.merkle_root = Some([1; 32]);
+    assert!(bid.check_terms(0).is_ok());
+
+    bid.expiry = 0;
+    assert!(bid.check_terms(i64::MAX).is_ok());
+    bid.quantity = 0;
+    assert!(bid.check_terms(0).is_err());
+}
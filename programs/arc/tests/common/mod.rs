@@ -0,0 +1,278 @@
+// shared by the proptest suite and the cargo-fuzz target
+
+use anchor_lang::{prelude::Pubkey, AnchorSerialize};
+use arc::{machine::Fill, state::Pool};
+
+// pool parameters as passed to init_pool, plus the settings later edit instructions apply
+#[derive(Clone, Debug, Default)]
+pub struct PoolConfig {
+    pub curve_type: u8,
+    pub pool_type: u8,
+    pub spot_price: u64,
+    pub delta: i64,
+    pub fee_bps: u64,
+    pub curator_fee_bps: u64,
+    pub floor_price: u64,
+    pub decay_rate: u64,
+    pub virtual_token_reserve: u64,
+    pub virtual_nft_reserve: u64,
+    pub min_price: u64,
+    pub max_price: u64,
+    pub spread_bps: u64,
+    pub flash_fee: u64,
+}
+
+// bounds keep a few hundred trades clear of u64 overflow
+const MAX_SPOT_PRICE: u64 = 1_000_000_000;
+const MAX_LINEAR_DELTA: u64 = 1_000_000;
+const MAX_EXPONENTIAL_DELTA: u64 = 20;
+const MAX_FEE_BPS: u64 = 1_000;
+const MAX_TOKEN_AMOUNT: u64 = 1_000_000_000_000;
+const MAX_VIRTUAL_NFTS: u64 = 100;
+const MAX_WAIT: u64 = 10_000;
+
+impl PoolConfig {
+    // maps raw proptest and fuzz values onto a config init_pool would accept
+    pub fn arbitrary(&self) -> PoolConfig {
+        let curve_type = self.curve_type % 4;
+        let spot_price = 1 + self.spot_price % MAX_SPOT_PRICE;
+        let max_delta = if curve_type == 1 {
+            MAX_EXPONENTIAL_DELTA
+        } else {
+            MAX_LINEAR_DELTA
+        }
+        .min(spot_price) as i64;
+
+        let mut config = PoolConfig {
+            curve_type,
+            // xyk pools are always TRADE
+            pool_type: if curve_type == 3 {
+                2
+            } else {
+                self.pool_type % 3
+            },
+            spot_price,
+            delta: self.delta % (max_delta + 1),
+            fee_bps: self.fee_bps % (MAX_FEE_BPS + 1),
+            curator_fee_bps: self.curator_fee_bps % (MAX_FEE_BPS + 1),
+            flash_fee: self.flash_fee % MAX_TOKEN_AMOUNT,
+            ..PoolConfig::default()
+        };
+        if curve_type == 2 {
+            config.floor_price = self.floor_price % (spot_price + 1);
+            config.decay_rate = self.decay_rate % 1_000;
+        }
+        if curve_type == 3 {
+            config.virtual_token_reserve = 1 + self.virtual_token_reserve % MAX_TOKEN_AMOUNT;
+            config.virtual_nft_reserve = 1 + self.virtual_nft_reserve % MAX_VIRTUAL_NFTS;
+        }
+        if config.pool_type == 2 {
+            config.spread_bps = self.spread_bps % (MAX_FEE_BPS + 1);
+        }
+        // bounds around the starting price, 0 and about a quarter of raw values leave no ceiling
+        let spot_price = config.build().spot_price;
+        config.min_price = self.min_price % (spot_price + 1);
+        if self.max_price != 0 && self.max_price < u64::MAX / 4 * 3 {
+            config.max_price = spot_price + self.max_price % MAX_SPOT_PRICE;
+        }
+        config
+    }
+
+    // the pool init_pool leaves behind, xyk pools price off their reserves
+    pub fn build(&self) -> Pool {
+        let mut pool = Pool {
+            curve_type: self.curve_type,
+            pool_type: self.pool_type,
+            spot_price: self.spot_price,
+            delta: self.delta,
+            fee_bps: self.fee_bps,
+            curator_fee_bps: self.curator_fee_bps,
+            floor_price: self.floor_price,
+            decay_rate: self.decay_rate,
+            virtual_token_reserve: self.virtual_token_reserve,
+            virtual_nft_reserve: self.virtual_nft_reserve,
+            min_price: self.min_price,
+            max_price: self.max_price,
+            spread_bps: self.spread_bps,
+            flash_fee: Some(self.flash_fee),
+            valid: true,
+            ..Pool::default()
+        };
+        if pool.curve_type == 3 {
+            pool.spot_price = pool.xyk_spot_price().unwrap();
+        }
+        pool
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Op {
+    Buy,
+    Sell,
+    DepositNft,
+    WithdrawNft,
+    DepositTokens(u64),
+    WithdrawTokens(u64),
+    TakeFees,
+    Wait(u64),
+    FlashBorrow,
+    FlashRepay,
+}
+
+impl Op {
+    pub fn from_raw(tag: u8, arg: u64) -> Op {
+        match tag % 10 {
+            0 => Op::Buy,
+            1 => Op::Sell,
+            2 => Op::DepositNft,
+            3 => Op::WithdrawNft,
+            4 => Op::DepositTokens(arg % MAX_TOKEN_AMOUNT),
+            5 => Op::WithdrawTokens(arg % MAX_TOKEN_AMOUNT),
+            6 => Op::TakeFees,
+            7 => Op::Wait(arg % MAX_WAIT),
+            8 => Op::FlashBorrow,
+            _ => Op::FlashRepay,
+        }
+    }
+}
+
+// a pool next to the token_vault balance and nft count the instructions would leave behind
+pub struct Sim {
+    pub pool: Pool,
+    pub vault: u64,
+    pub nfts: u64,
+    // nfts out on a flash loan, still counted in n_nft
+    pub lent: u64,
+    pub now: i64,
+}
+
+impl Sim {
+    pub fn new(pool: Pool) -> Sim {
+        Sim {
+            pool,
+            vault: 0,
+            nfts: 0,
+            lent: 0,
+            now: 0,
+        }
+    }
+
+    // applies op as its instruction would, a failed op must leave the pool as it was
+    pub fn step(&mut self, op: &Op) {
+        if let Op::Wait(seconds) = op {
+            self.now += *seconds as i64;
+            return;
+        }
+        self.pool.apply_decay(self.now);
+        let before = snapshot(&self.pool);
+
+        let ok = match op {
+            Op::Buy => self.pool.buy().map(|fill| {
+                self.vault += fill.cost().unwrap();
+                self.nfts = self
+                    .nfts
+                    .checked_sub(1)
+                    .expect("sold an nft it does not hold");
+            }),
+            Op::Sell => self.pool.sell().map(|fill| {
+                self.vault = (self.vault.checked_sub(fill.price))
+                    .expect("paid out more than the vault holds")
+                    + fill.fees().unwrap();
+                self.nfts += 1;
+            }),
+            Op::DepositNft => self.pool.deposit_nft().map(|_| self.nfts += 1),
+            Op::WithdrawNft => self.pool.withdraw_nft().map(|_| {
+                self.nfts = self
+                    .nfts
+                    .checked_sub(1)
+                    .expect("withdrew an nft it does not hold");
+            }),
+            Op::DepositTokens(amount) => self
+                .pool
+                .deposit_tokens(*amount)
+                .map(|_| self.vault += amount),
+            Op::WithdrawTokens(amount) => self.pool.withdraw_tokens(*amount).map(|_| {
+                self.vault = self.vault.checked_sub(*amount).expect("vault overdrawn");
+            }),
+            Op::TakeFees => {
+                let fees = self.pool.take_fees();
+                self.vault = self.vault.checked_sub(fees).expect("vault overdrawn");
+                Ok(())
+            }
+            // every test nft shares one mint
+            Op::FlashBorrow => self.pool.lend_nft(Pubkey::default()).map(|_| {
+                self.nfts = self
+                    .nfts
+                    .checked_sub(1)
+                    .expect("lent an nft it does not hold");
+                self.lent += 1;
+            }),
+            Op::FlashRepay => self.pool.repay_nft(Pubkey::default()).map(|fee| {
+                self.nfts += 1;
+                self.lent -= 1;
+                self.vault += fee;
+            }),
+            Op::Wait(_) => unreachable!(),
+        }
+        .is_ok();
+
+        if !ok {
+            assert_eq!(
+                snapshot(&self.pool),
+                before,
+                "{:?} failed but changed the pool",
+                op
+            );
+        }
+        self.check();
+    }
+
+    pub fn check(&self) {
+        let pool = &self.pool;
+        assert_eq!(
+            self.vault,
+            pool.n_token + pool.accrued_fees,
+            "token_vault balance != n_token + accrued_fees"
+        );
+        assert_eq!(self.nfts + self.lent, pool.n_nft, "nft count != n_nft");
+        assert!(self.lent <= 1, "more than one flash loan outstanding");
+        assert!(pool.total_fees >= pool.accrued_fees);
+        // xyk prices follow the reserves, so only trades are held to the bounds
+        if pool.curve_type != 3 {
+            assert!(
+                pool.check_price_bounds(pool.spot_price).is_ok(),
+                "spot_price left its bounds"
+            );
+        }
+    }
+}
+
+// buying then selling straight back, or selling then buying back, never nets the taker tokens
+pub fn check_round_trips(pool: &Pool) {
+    let mut buy_first = pool.clone();
+    if let Ok(buy) = buy_first.buy() {
+        if let Ok(sell) = buy_first.sell() {
+            assert_round_trip(buy, sell);
+        }
+    }
+
+    let mut sell_first = pool.clone();
+    if let Ok(sell) = sell_first.sell() {
+        if let Ok(buy) = sell_first.buy() {
+            assert_round_trip(buy, sell);
+        }
+    }
+}
+
+fn assert_round_trip(buy: Fill, sell: Fill) {
+    assert!(
+        sell.proceeds().unwrap() <= buy.cost().unwrap(),
+        "round trip profit: paid {:?}, received {:?}",
+        buy,
+        sell
+    );
+}
+
+fn snapshot(pool: &Pool) -> Vec<u8> {
+    pool.try_to_vec().unwrap()
+}
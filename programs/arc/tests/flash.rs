@@ -0,0 +1,98 @@
+use anchor_lang::{
+    prelude::{AccountInfo, Pubkey},
+    solana_program::sysvar::instructions::{
+        self as sysvar_instructions, construct_instructions_data, store_current_index,
+        BorrowedAccountMeta, BorrowedInstruction,
+    },
+    InstructionData,
+};
+use arc::utils::assert_repay_follows;
+
+// a transaction as the instructions sysvar lays it out, executing instruction current
+struct Transaction {
+    data: Vec<u8>,
+    lamports: u64,
+}
+
+impl Transaction {
+    // each instruction is an arc instruction's data and its account keys
+    fn new(instructions: &[(Vec<u8>, Vec<Pubkey>)], current: u16) -> Transaction {
+        let borrowed: Vec<BorrowedInstruction> = instructions
+            .iter()
+            .map(|(data, accounts)| BorrowedInstruction {
+                program_id: &arc::ID,
+                accounts: accounts
+                    .iter()
+                    .map(|pubkey| BorrowedAccountMeta {
+                        pubkey,
+                        is_signer: false,
+                        is_writable: false,
+                    })
+                    .collect(),
+                data,
+            })
+            .collect();
+        let mut data = construct_instructions_data(&borrowed);
+        store_current_index(&mut data, current);
+        Transaction { data, lamports: 0 }
+    }
+
+    fn check(&mut self, pool: Pubkey, nft_mint: Pubkey, borrower: Pubkey) -> bool {
+        let key = sysvar_instructions::ID;
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut self.lamports,
+            &mut self.data,
+            &key,
+            false,
+            0,
+        );
+        assert_repay_follows(&info, pool, nft_mint, borrower).is_ok()
+    }
+}
+
+// accounts of FlashRepay in order, the scan reads pool, nft_mint and borrower
+fn repay(pool: Pubkey, nft_mint: Pubkey, borrower: Pubkey) -> (Vec<u8>, Vec<Pubkey>) {
+    let other = Pubkey::new_unique();
+    (
+        arc::instruction::FlashRepay {}.data(),
+        vec![pool, other, nft_mint, other, other, other, borrower, other],
+    )
+}
+
+fn borrow() -> (Vec<u8>, Vec<Pubkey>) {
+    (arc::instruction::FlashBorrow {}.data(), vec![])
+}
+
+#[test]
+fn borrow_needs_a_matching_repay() {
+    let (pool, nft_mint, borrower) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let mut tx = Transaction::new(&[borrow(), repay(pool, nft_mint, borrower)], 0);
+    assert!(tx.check(pool, nft_mint, borrower));
+    // a repay for another pool, nft or borrower does not count
+    assert!(!tx.check(Pubkey::new_unique(), nft_mint, borrower));
+    assert!(!tx.check(pool, Pubkey::new_unique(), borrower));
+    assert!(!tx.check(pool, nft_mint, Pubkey::new_unique()));
+}
+
+#[test]
+fn borrow_without_repay_fails() {
+    let (pool, nft_mint, borrower) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    assert!(!Transaction::new(&[borrow()], 0).check(pool, nft_mint, borrower));
+    assert!(!Transaction::new(&[borrow(), borrow()], 0).check(pool, nft_mint, borrower));
+    // a repay earlier in the transaction does not cover the borrow
+    assert!(
+        !Transaction::new(&[repay(pool, nft_mint, borrower), borrow()], 1)
+            .check(pool, nft_mint, borrower)
+    );
+}
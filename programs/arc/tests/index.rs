@@ -0,0 +1,47 @@
+use anchor_lang::prelude::Pubkey;
+use arc::state::{CollectionIndex, Pool, COLLECTION_INDEX_CAPACITY};
+
+#[test]
+fn full_page_sends_pools_to_the_next() {
+    let collection = Pubkey::new_unique();
+    let mut first = CollectionIndex {
+        key: collection.to_bytes(),
+        ..CollectionIndex::default()
+    };
+    for _ in 0..COLLECTION_INDEX_CAPACITY {
+        first.register(Pubkey::new_unique(), 100).unwrap();
+    }
+    assert!(first.is_full());
+    assert!(first.register(Pubkey::new_unique(), 100).is_err());
+
+    let mut second = CollectionIndex {
+        key: collection.to_bytes(),
+        page: 1,
+        ..CollectionIndex::default()
+    };
+    let mut pool = Pool {
+        collection: Some(collection),
+        ..Pool::default()
+    };
+    second.register(Pubkey::new_unique(), 100).unwrap();
+    pool.index_page = second.page;
+    assert!(pool.is_indexed_in(&second));
+    assert!(!pool.is_indexed_in(&first));
+
+    // a page of another collection never matches
+    let other = CollectionIndex {
+        page: 1,
+        ..CollectionIndex::default()
+    };
+    assert!(!pool.is_indexed_in(&other));
+}
+
+#[test]
+fn unrestricted_pools_index_by_root_or_zeroes() {
+    let mut pool = Pool::default();
+    assert_eq!(pool.index_key(), [0; 32]);
+    pool.merkle_root = Some([7; 32]);
+    assert_eq!(pool.index_key(), [7; 32]);
+    pool.collection = Some(Pubkey::new_unique());
+    assert_eq!(pool.index_key(), pool.collection.unwrap().to_bytes());
+}
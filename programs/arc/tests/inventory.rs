@@ -0,0 +1,124 @@
+use anchor_lang::prelude::Pubkey;
+use arc::{
+    state::{Inventory, Pool, BASE_MULTIPLIER_BPS, INVENTORY_CAPACITY},
+    utils::nft_leaf,
+};
+
+fn page(pool: &mut Pool, page: u32) -> Inventory {
+    pool.inventory_pages = page + 1;
+    Inventory {
+        page,
+        ..Inventory::default()
+    }
+}
+
+#[test]
+fn full_page_moves_deposits_to_the_next() {
+    let mut pool = Pool::default();
+    let mut first = page(&mut pool, 0);
+    for _ in 0..INVENTORY_CAPACITY {
+        first.insert(&pool, Pubkey::new_unique()).unwrap();
+    }
+    assert!(first.insert(&pool, Pubkey::new_unique()).is_err());
+
+    let mut second = page(&mut pool, 1);
+    let newest = Pubkey::new_unique();
+    second.insert(&pool, newest).unwrap();
+    // older pages only drain once a newer page exists
+    first.remove(&mut pool, first.mints[0]).unwrap();
+    assert!(first.insert(&pool, Pubkey::new_unique()).is_err());
+    assert_eq!(second.mints, vec![newest]);
+}
+
+#[test]
+fn oldest_comes_off_the_head_page() {
+    let mut pool = Pool::default();
+    let mut first = page(&mut pool, 0);
+    let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+    first.insert(&pool, a).unwrap();
+    first.insert(&pool, b).unwrap();
+    let mut second = page(&mut pool, 1);
+    let c = Pubkey::new_unique();
+    second.insert(&pool, c).unwrap();
+
+    assert!(second.oldest(&pool).is_err());
+    assert_eq!(first.oldest(&pool).unwrap(), a);
+    first.remove(&mut pool, a).unwrap();
+    assert_eq!(first.oldest(&pool).unwrap(), b);
+
+    // draining the head page hands fifo to the next page
+    first.remove(&mut pool, b).unwrap();
+    assert_eq!(pool.inventory_head, 1);
+    assert_eq!(second.oldest(&pool).unwrap(), c);
+
+    // the newest page stays the head even once empty
+    second.remove(&mut pool, c).unwrap();
+    assert_eq!(pool.inventory_head, 1);
+}
+
+#[test]
+fn head_cranks_past_pages_emptied_out_of_order() {
+    let mut pool = Pool::default();
+    let mut first = page(&mut pool, 0);
+    let a = Pubkey::new_unique();
+    first.insert(&pool, a).unwrap();
+    let mut second = page(&mut pool, 1);
+    let b = Pubkey::new_unique();
+    second.insert(&pool, b).unwrap();
+    let mut third = page(&mut pool, 2);
+    let c = Pubkey::new_unique();
+    third.insert(&pool, c).unwrap();
+
+    second.remove(&mut pool, b).unwrap();
+    assert_eq!(pool.inventory_head, 0);
+    first.remove(&mut pool, a).unwrap();
+    assert_eq!(pool.inventory_head, 1);
+    assert!(second.oldest(&pool).is_err());
+
+    assert!(!pool.advance_inventory_head(&third));
+    assert!(pool.advance_inventory_head(&second));
+    assert_eq!(third.oldest(&pool).unwrap(), c);
+}
+
+#[test]
+fn rechecked_root_skips_stale_inventory() {
+    let mut pool = Pool::default();
+    let mut first = page(&mut pool, 0);
+    let (stale, fresh) = (Pubkey::new_unique(), Pubkey::new_unique());
+    first.insert(&pool, stale).unwrap();
+    first.insert(&pool, fresh).unwrap();
+    let mut second = page(&mut pool, 1);
+    let newest = Pubkey::new_unique();
+    second.insert(&pool, newest).unwrap();
+
+    // without a recheck the head's oldest still sells
+    assert_eq!(
+        first
+            .oldest_provable(&pool, BASE_MULTIPLIER_BPS, &[])
+            .unwrap(),
+        (stale, BASE_MULTIPLIER_BPS)
+    );
+
+    // rotated to a single-leaf root over fresh, which is its own root
+    pool.merkle_root = Some(nft_leaf(fresh));
+    pool.recheck_inventory = true;
+    assert_eq!(
+        first
+            .oldest_provable(&pool, BASE_MULTIPLIER_BPS, &[])
+            .unwrap(),
+        (fresh, BASE_MULTIPLIER_BPS)
+    );
+
+    // a head holding only stale nfts does not block later pages
+    first.remove(&mut pool, fresh).unwrap();
+    pool.merkle_root = Some(nft_leaf(newest));
+    assert!(first
+        .oldest_provable(&pool, BASE_MULTIPLIER_BPS, &[])
+        .is_err());
+    assert_eq!(
+        second
+            .oldest_provable(&pool, BASE_MULTIPLIER_BPS, &[])
+            .unwrap(),
+        (newest, BASE_MULTIPLIER_BPS)
+    );
+}
@@ -0,0 +1,557 @@
+mod common;
+
+use anchor_lang::{
+    prelude::{AccountInfo, Pubkey},
+    AccountSerialize,
+};
+use arc::state::{BuyerRecord, Pool, PriceFeed, MAX_REFERRAL_BPS};
+use common::{check_round_trips, Op, PoolConfig, Sim};
+use proptest::prelude::*;
+
+fn config() -> impl Strategy<Value = PoolConfig> {
+    (
+        (
+            any::<u8>(),
+            any::<u8>(),
+            any::<u64>(),
+            any::<i64>(),
+            any::<u64>(),
+        ),
+        (
+            any::<u64>(),
+            any::<u64>(),
+            any::<u64>(),
+            any::<u64>(),
+            any::<u64>(),
+        ),
+        (any::<u64>(), any::<u64>(), any::<u64>(), any::<u64>()),
+    )
+        .prop_map(|(a, b, c)| PoolConfig {
+            curve_type: a.0,
+            pool_type: a.1,
+            spot_price: a.2,
+            delta: a.3,
+            fee_bps: a.4,
+            curator_fee_bps: b.0,
+            floor_price: b.1,
+            decay_rate: b.2,
+            virtual_token_reserve: b.3,
+            virtual_nft_reserve: b.4,
+            min_price: c.0,
+            max_price: c.1,
+            spread_bps: c.2,
+            flash_fee: c.3,
+        })
+}
+
+fn ops() -> impl Strategy<Value = Vec<Op>> {
+    prop::collection::vec(
+        (any::<u8>(), any::<u64>()).prop_map(|(tag, arg)| Op::from_raw(tag, arg)),
+        0..64,
+    )
+}
+
+proptest! {
+    #[test]
+    fn bookkeeping_matches_vault(config in config(), ops in ops()) {
+        let mut sim = Sim::new(config.arbitrary().build());
+        for op in ops.iter() {
+            sim.step(op);
+        }
+    }
+
+    #[test]
+    fn round_trips_never_profit(config in config(), ops in ops()) {
+        let mut sim = Sim::new(config.arbitrary().build());
+        for op in ops.iter() {
+            sim.step(op);
+            check_round_trips(&sim.pool);
+        }
+    }
+}
+
+#[test]
+fn empty_pool_rejects_buy() {
+    let mut pool = PoolConfig {
+        pool_type: 2,
+        spot_price: 100,
+        delta: 10,
+        ..PoolConfig::default()
+    }
+    .build();
+    assert!(pool.buy().is_err());
+    assert!(pool.withdraw_nft().is_err());
+    assert_eq!(pool.n_nft, 0);
+}
+
+#[test]
+fn linear_round_trip_returns_price() {
+    let mut sim = Sim::new(
+        PoolConfig {
+            pool_type: 2,
+            spot_price: 100,
+            delta: 10,
+            ..PoolConfig::default()
+        }
+        .build(),
+    );
+    sim.step(&Op::DepositNft);
+    sim.step(&Op::DepositTokens(1_000));
+
+    let buy = sim.pool.buy().unwrap();
+    let sell = sim.pool.sell().unwrap();
+    assert_eq!(buy.price, 100);
+    assert_eq!(sell.price, 100);
+    assert_eq!(sim.pool.spot_price, 100);
+}
+
+#[test]
+fn overflow_is_an_error() {
+    let mut pool = Pool {
+        curve_type: 1,
+        pool_type: 2,
+        spot_price: u64::MAX,
+        delta: 50,
+        fee_bps: 100,
+        n_nft: 1,
+        n_token: u64::MAX,
+        ..Pool::default()
+    };
+    assert!(pool.buy().is_err());
+    assert!(pool.sell().is_err());
+    assert_eq!(pool.n_nft, 1);
+}
+
+#[test]
+fn bounds_stop_the_curve() {
+    let mut pool = PoolConfig {
+        pool_type: 2,
+        spot_price: 100,
+        delta: 10,
+        ..PoolConfig::default()
+    }
+    .build();
+    pool.min_price = 80;
+    pool.max_price = 120;
+    let mut sim = Sim::new(pool);
+    sim.step(&Op::DepositTokens(1_000));
+    sim.step(&Op::DepositNft);
+    sim.step(&Op::DepositNft);
+    assert_eq!(sim.pool.price_status(), 0);
+
+    // sells walk spot down to the floor, then stop
+    sim.step(&Op::Sell);
+    sim.step(&Op::Sell);
+    assert_eq!(sim.pool.spot_price, 80);
+    assert_eq!(sim.pool.price_status(), 1);
+    assert!(sim.pool.sell().is_err());
+
+    // buys walk it up to the ceiling, then stop
+    for _ in 0..4 {
+        sim.step(&Op::Buy);
+    }
+    assert_eq!(sim.pool.spot_price, 120);
+    assert_eq!(sim.pool.price_status(), 2);
+    assert!(sim.pool.buy().is_err());
+    assert_eq!(sim.pool.n_nft, 0);
+}
+
+#[test]
+fn ceiling_caps_exponential_growth() {
+    let mut pool = Pool {
+        curve_type: 1,
+        pool_type: 1,
+        spot_price: 1_000,
+        delta: 50,
+        n_nft: 100,
+        max_price: 1_000_000,
+        ..Pool::default()
+    };
+    while pool.buy().is_ok() {}
+    assert!(pool.spot_price <= 1_000_000);
+    assert_eq!(pool.price_status(), 2);
+}
+
+#[test]
+fn edit_delta_checks_the_curve() {
+    let mut pool = PoolConfig {
+        curve_type: 1,
+        pool_type: 2,
+        spot_price: 1_000,
+        delta: 10,
+        ..PoolConfig::default()
+    }
+    .build();
+    // exponential deltas are percentages below 100
+    assert!(pool.edit_delta(100).is_err());
+    assert_eq!(pool.delta, 10);
+    pool.edit_delta(-20).unwrap();
+    assert_eq!(pool.delta, -20);
+
+    // linear steps cannot exceed the price
+    pool.curve_type = 0;
+    assert!(pool.edit_delta(1_001).is_err());
+    pool.edit_delta(1_000).unwrap();
+
+    // oracle prices never step
+    pool.curve_type = 4;
+    assert!(pool.edit_delta(1).is_err());
+}
+
+#[test]
+fn edit_spot_price_keeps_delta_and_bounds() {
+    let mut pool = PoolConfig {
+        pool_type: 2,
+        spot_price: 100,
+        delta: 10,
+        min_price: 50,
+        max_price: 200,
+        ..PoolConfig::default()
+    }
+    .build();
+    assert!(pool.edit_spot_price(0).is_err());
+    // the current delta would step below zero
+    pool.min_price = 0;
+    assert!(pool.edit_spot_price(9).is_err());
+    pool.min_price = 50;
+    assert!(pool.edit_spot_price(49).is_err());
+    assert!(pool.edit_spot_price(201).is_err());
+    assert_eq!(pool.spot_price, 100);
+
+    pool.edit_spot_price(150).unwrap();
+    assert_eq!(pool.spot_price, 150);
+}
+
+#[test]
+fn token_deposits_leave_fees_apart() {
+    let mut sim = Sim::new(
+        PoolConfig {
+            pool_type: 2,
+            spot_price: 1_000,
+            delta: 10,
+            fee_bps: 500,
+            ..PoolConfig::default()
+        }
+        .build(),
+    );
+    sim.step(&Op::DepositTokens(10_000));
+    sim.step(&Op::DepositNft);
+    sim.step(&Op::Buy);
+    sim.step(&Op::Sell);
+    assert!(sim.pool.accrued_fees > 0);
+
+    // add_token_liquidity syncs against the token_vault swaps pay fees into, tokens sent
+    // straight to it become principal
+    sim.vault += 100;
+    let n_token = sim.pool.n_token;
+    sim.pool.sync_n_token(sim.vault).unwrap();
+    assert_eq!(sim.pool.n_token, n_token + 100);
+    sim.step(&Op::DepositTokens(5_000));
+    assert_eq!(sim.pool.n_token + sim.pool.accrued_fees, sim.vault);
+}
+
+#[test]
+fn spread_widens_the_sell_side() {
+    let mut pool = PoolConfig {
+        pool_type: 2,
+        spot_price: 1_000,
+        delta: 100,
+        spread_bps: 500,
+        ..PoolConfig::default()
+    }
+    .build();
+    pool.n_nft = 1;
+    pool.n_token = 10_000;
+
+    // one curve step below the buy price, then 5% off that
+    let (buy, sell) = pool.quote();
+    assert_eq!(buy.unwrap().price, 1_000);
+    assert_eq!(sell.unwrap().price, 855);
+
+    // the spread stays in the pool as principal
+    let fill = pool.sell().unwrap();
+    assert_eq!(pool.n_token, 10_000 - fill.price);
+    assert_eq!(pool.spot_price, 900);
+}
+
+#[test]
+fn expiry_stops_trading() {
+    let pool = Pool {
+        expires_at: 1_000,
+        ..Pool::default()
+    };
+    assert!(pool.check_expiry(999).is_ok());
+    assert!(pool.check_expiry(1_000).is_err());
+    assert!(!Pool::default().is_expired(i64::MAX));
+}
+
+#[test]
+fn buy_limit_caps_a_wallet() {
+    let mut record = BuyerRecord::default();
+    record.record_buy(2).unwrap();
+    record.record_buy(2).unwrap();
+    assert!(record.record_buy(2).is_err());
+    assert_eq!(record.bought, 2);
+
+    // raising the limit lets the wallet buy again
+    record.record_buy(3).unwrap();
+}
+
+#[test]
+fn referral_is_capped() {
+    let mut pool = Pool::default();
+    assert_eq!(pool.referral_fee(10_000, MAX_REFERRAL_BPS).unwrap(), 200);
+    assert!(pool.referral_fee(10_000, MAX_REFERRAL_BPS + 1).is_err());
+
+    pool.max_referral_bps = 50;
+    assert_eq!(pool.referral_fee(10_000, 50).unwrap(), 50);
+    assert!(pool.referral_fee(10_000, 51).is_err());
+}
+
+#[test]
+fn shared_pools_freeze_owner_settings() {
+    let mut pool = PoolConfig {
+        pool_type: 2,
+        spot_price: 100,
+        delta: 10,
+        flash_fee: 5,
+        ..PoolConfig::default()
+    }
+    .build();
+    pool.rental_rate = Some(5);
+    pool.n_nft = 2;
+    pool.lp_mint = Some(Pubkey::new_unique());
+
+    assert!(pool.edit_spot_price(1).is_err());
+    assert!(pool.edit_delta(0).is_err());
+    assert!(pool.check_not_shared().is_err());
+    // lp shares count nfts in the vault, so none can leave on a loan or rental
+    assert!(pool.lend_nft(Pubkey::new_unique()).is_err());
+    assert!(pool.rent_nft(1).is_err());
+    assert_eq!((pool.spot_price, pool.delta, pool.n_nft), (100, 10, 2));
+}
+
+#[test]
+fn flash_loan_locks_nft_trades() {
+    let mut pool = Pool {
+        pool_type: 2,
+        spot_price: 100,
+        n_nft: 1,
+        n_token: 1_000,
+        flash_fee: Some(5),
+        ..Pool::default()
+    };
+    let mint = Pubkey::new_unique();
+    pool.lend_nft(mint).unwrap();
+    assert!(pool.lend_nft(mint).is_err());
+    assert!(pool.buy().is_err());
+    assert!(pool.sell().is_err());
+    assert!(pool.withdraw_nft().is_err());
+    assert!(pool.repay_nft(Pubkey::new_unique()).is_err());
+
+    assert_eq!(pool.repay_nft(mint).unwrap(), 5);
+    assert_eq!(pool.accrued_fees, 5);
+    assert!(pool.buy().is_ok());
+}
+
+#[test]
+fn batch_deposits_match_single_deposits() {
+    let base = Pool {
+        curve_type: 3,
+        pool_type: 2,
+        virtual_token_reserve: 1_000,
+        virtual_nft_reserve: 10,
+        n_token: 5_000,
+        ..Pool::default()
+    };
+    let mut single = base.clone();
+    for _ in 0..5 {
+        single.deposit_nft().unwrap();
+    }
+    let mut batch = base.clone();
+    batch.deposit_nfts(5).unwrap();
+    assert_eq!(batch.n_nft, single.n_nft);
+    assert_eq!(batch.spot_price, single.spot_price);
+
+    // a batch larger than the inventory withdraws nothing
+    assert!(batch.withdraw_nfts(6).is_err());
+    assert_eq!(batch.n_nft, 5);
+    batch.withdraw_nfts(5).unwrap();
+    assert_eq!(batch.spot_price, base.clone().xyk_spot_price().unwrap());
+}
+
+#[test]
+fn multiplier_scales_price_not_curve() {
+    let pool = Pool {
+        pool_type: 2,
+        spot_price: 1_000,
+        delta: 100,
+        fee_bps: 100,
+        n_nft: 2,
+        n_token: 10_000,
+        ..Pool::default()
+    };
+
+    let mut base = pool.clone();
+    let mut rare = pool.clone();
+    let base_fill = base.buy().unwrap();
+    let rare_fill = rare.buy_at(15_000).unwrap();
+    assert_eq!(rare_fill.price, base_fill.price * 3 / 2);
+    assert_eq!(rare_fill.pool_fee, rare_fill.price / 100);
+    assert_eq!(rare.spot_price, base.spot_price);
+
+    let (buy, sell) = pool.quote_at(5_000);
+    assert_eq!(buy.unwrap().price, 500);
+    assert_eq!(sell.unwrap().price, 450);
+    assert!(pool.clone().sell_at(0).is_err());
+}
+
+#[test]
+fn oracle_pool_tracks_its_feed() {
+    let mut pool = Pool {
+        curve_type: 4,
+        pool_type: 2,
+        n_nft: 2,
+        n_token: 10_000,
+        oracle: Some(Pubkey::new_unique()),
+        oracle_bps: 9_500,
+        max_staleness: 60,
+        ..Pool::default()
+    };
+    // never synced
+    assert!(pool.check_oracle(1_000).is_err());
+
+    pool.sync_oracle(1_000, 1_000).unwrap();
+    assert_eq!(pool.spot_price, 950);
+    assert!(pool.check_oracle(1_060).is_ok());
+    assert!(pool.check_oracle(1_061).is_err());
+
+    // trades do not move the price, only the next sync does
+    assert_eq!(pool.buy().unwrap().price, 950);
+    assert_eq!(pool.sell().unwrap().price, 950);
+    assert_eq!(pool.spot_price, 950);
+    pool.sync_oracle(2_000, 1_100).unwrap();
+    assert_eq!(pool.spot_price, 1_900);
+
+    // a feed outside the bounds leaves the last price, and the pool goes stale
+    pool.max_price = 2_000;
+    assert!(pool.sync_oracle(3_000, 1_200).is_err());
+    assert_eq!(pool.spot_price, 1_900);
+    assert!(pool.check_oracle(1_200).is_err());
+
+    assert!(Pool::default().sync_oracle(1_000, 0).is_err());
+}
+
+#[test]
+fn oracle_trades_price_off_the_feed() {
+    let key = Pubkey::new_unique();
+    let mut data = Vec::new();
+    PriceFeed {
+        authority: Pubkey::new_unique(),
+        price: 2_000,
+        updated_at: 1_000,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let mut lamports = 0;
+    let feed = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &arc::ID,
+        false,
+        0,
+    );
+
+    let mut pool = Pool {
+        curve_type: 4,
+        pool_type: 2,
+        spot_price: 950,
+        oracle: Some(key),
+        oracle_bps: 9_500,
+        max_staleness: 60,
+        oracle_updated_at: 990,
+        ..Pool::default()
+    };
+    // the owner cannot move an oracle pool's price by hand
+    assert!(pool.edit_spot_price(10_000).is_err());
+    assert!(pool.edit_delta(0).is_err());
+
+    // a trade without the feed, or with another account, does not fall back to the synced price
+    assert!(pool.sync_price_feed(&mut [].iter()).is_err());
+    let other = AccountInfo {
+        key: &Pubkey::new_unique(),
+        ..feed.clone()
+    };
+    assert!(pool.sync_price_feed(&mut [other].iter()).is_err());
+    assert_eq!(pool.spot_price, 950);
+
+    pool.sync_price_feed(&mut [feed].iter()).unwrap();
+    assert_eq!(pool.spot_price, 1_900);
+    assert_eq!(pool.oracle_updated_at, 1_000);
+
+    // other curves take no feed
+    let mut linear = Pool::default();
+    linear.sync_price_feed(&mut [].iter()).unwrap();
+}
+
+#[test]
+fn route_fills_from_the_cheapest_pool() {
+    // as init_pool leaves a pool, then stocked through the machine
+    let pool = |spot_price| {
+        let mut pool = Pool {
+            curve_type: 0,
+            pool_type: 2,
+            spot_price,
+            delta: 10,
+            valid: true,
+            ..Pool::default()
+        };
+        pool.deposit_nfts(2).unwrap();
+        pool
+    };
+    let private = Pool {
+        taker_root: Some([1; 32]),
+        ..pool(50)
+    };
+    let mut pools = [pool(120), pool(100), private, pool(200)];
+
+    let mut fills = Vec::new();
+    for _ in 0..5 {
+        let (i, price) = pools
+            .iter()
+            .enumerate()
+            .filter_map(|(i, pool)| pool.route_buy_quote().map(|price| (i, price)))
+            .min_by_key(|(_, price)| *price)
+            .unwrap();
+        assert_eq!(pools[i].buy().unwrap().cost().unwrap(), price);
+        fills.push((i, price));
+    }
+    assert_eq!(
+        fills,
+        vec![(1, 100), (1, 110), (0, 120), (0, 130), (3, 200)]
+    );
+    assert_eq!(pools[1].route_buy_quote(), None);
+    assert_eq!(pools[2].route_buy_quote(), None);
+    assert!(pools[1].route_sell_quote().is_some());
+}
+
+#[test]
+fn dutch_sells_stop_at_floor() {
+    let mut pool = Pool {
+        curve_type: 2,
+        pool_type: 2,
+        spot_price: 105,
+        delta: 10,
+        floor_price: 100,
+        n_token: 10_000,
+        valid: true,
+        ..Pool::default()
+    };
+    pool.sell().unwrap();
+    assert_eq!(pool.spot_price, 100);
+    assert_eq!(pool.sell().unwrap().price, 100);
+    assert_eq!(pool.spot_price, 100);
+}
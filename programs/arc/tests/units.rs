@@ -0,0 +1,53 @@
+use arc::{
+    state::Pool,
+    units::{format_units, parse_units},
+};
+
+#[test]
+fn parses_human_units() {
+    assert_eq!(parse_units("1.25", 9).unwrap(), 1_250_000_000);
+    assert_eq!(parse_units("0.000000001", 9).unwrap(), 1);
+    assert_eq!(parse_units(".5", 2).unwrap(), 50);
+    assert_eq!(parse_units("7", 0).unwrap(), 7);
+    assert_eq!(parse_units("1.500", 2).unwrap(), 150);
+}
+
+#[test]
+fn rejects_amounts_finer_than_precision() {
+    assert!(parse_units("0.001", 2).is_err());
+    assert!(parse_units("1.5", 0).is_err());
+}
+
+#[test]
+fn rejects_malformed_amounts() {
+    for amount in ["", ".", "1.2.3", "-1", "1e9", " 1"] {
+        assert!(parse_units(amount, 6).is_err(), "{:?}", amount);
+    }
+    assert!(parse_units("18446744073709551616", 0).is_err());
+    assert!(parse_units("18446744073.709551616", 9).is_err());
+}
+
+#[test]
+fn formats_base_units() {
+    assert_eq!(format_units(1_250_000_000, 9), "1.25");
+    assert_eq!(format_units(1, 9), "0.000000001");
+    assert_eq!(format_units(100, 2), "1");
+    assert_eq!(format_units(42, 0), "42");
+    for amount in [0, 1, 999, 1_000_000, u64::MAX] {
+        assert_eq!(parse_units(&format_units(amount, 6), 6).unwrap(), amount);
+    }
+}
+
+#[test]
+fn pool_prices_use_mint_decimals() {
+    let pool = Pool {
+        decimals: 6,
+        spot_price: 2_500_000,
+        pool_type: 1,
+        ..Pool::default()
+    };
+    assert_eq!(pool.parse_price("2.5").unwrap(), 2_500_000);
+    assert!(pool.parse_price("0").is_err());
+    assert!(pool.parse_price("0.0000001").is_err());
+    assert_eq!(pool.buy_quote_ui().unwrap(), "2.5");
+}